@@ -0,0 +1,194 @@
+//! Construction of key destruction proofs, key batch messages and WOTS+ signatures
+//! for shift-encumbrance.
+//!
+//! Proofs are built on the device or client, never on-chain, so that key material
+//! hashes stay off the ledger. The crate is `no_std` and allocation-free so it can
+//! run in device firmware.
+
+#![no_std]
+
+use sha2::{Digest, Sha256};
+
+pub mod wots;
+
+/// SHA-256 over the concatenation of `parts`. With the `solana` feature this goes through
+/// the runtime's hashing syscall, which costs far less compute than hashing in the program.
+#[cfg(feature = "solana")]
+pub fn hashv(parts: &[&[u8]]) -> [u8; 32] {
+    solana_program::hash::hashv(parts).to_bytes()
+}
+
+/// SHA-256 over the concatenation of `parts`
+#[cfg(not(feature = "solana"))]
+pub fn hashv(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// Size of `KeyDestructionProof::proof_data`
+pub const PROOF_DATA_LEN: usize = 256;
+
+/// Hash-based stand-in for a zero-knowledge proof of key destruction
+pub fn create_destruction_proof_data(
+    device_id: &[u8; 32],
+    private_key_hash: &[u8; 32],
+    public_key: &[u8; 32],
+    nonce: &[u8; 32],
+) -> [u8; PROOF_DATA_LEN] {
+    // In production, this would generate a ZK-SNARK proof
+    // For demo purposes, we'll create a hash-based proof
+    let mut hasher = Sha256::new();
+    hasher.update(device_id);
+    hasher.update(private_key_hash);
+    hasher.update(public_key);
+    hasher.update(nonce);
+    hasher.update(b"SHIFT_KEY_DESTRUCTION_PROOF");
+
+    let hash = hasher.finalize();
+    let mut proof_data = [0u8; PROOF_DATA_LEN];
+
+    // Fill the proof data with multiple hashes for demonstration
+    for i in 0..8 {
+        let mut hasher = Sha256::new();
+        hasher.update(hash);
+        hasher.update((i as u64).to_le_bytes());
+        let iteration_hash = hasher.finalize();
+        proof_data[i * 32..(i + 1) * 32].copy_from_slice(&iteration_hash);
+    }
+
+    proof_data
+}
+
+/// Message the device's attested key signs for a HardwareAttestation proof
+pub fn destruction_message(public_key: &[u8; 32], transaction_hash: &[u8; 32], nonce: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"SHIFT_KEY_DESTRUCTION");
+    hasher.update(public_key);
+    hasher.update(transaction_hash);
+    hasher.update(nonce);
+    hasher.finalize().into()
+}
+
+/// Message the device's attested key signs to add a batch of keys to its pool
+pub fn key_batch_message(device_id: &[u8; 32], merkle_root: &[u8; 32], first_index: u32, key_count: u32) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"SHIFT_KEY_BATCH");
+    hasher.update(device_id);
+    hasher.update(merkle_root);
+    hasher.update(first_index.to_le_bytes());
+    hasher.update(key_count.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Commitment to a key's destruction reveal. It is fixed in the key's Merkle leaf when
+/// the key's batch is generated, and the device only releases `reveal` when it destroys the key.
+pub fn destruction_commitment(public_key: &[u8; 32], reveal: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"SHIFT_KEY_COMMITMENT");
+    hasher.update(public_key);
+    hasher.update(reveal);
+    hasher.finalize().into()
+}
+
+/// Tag binding a key's destruction reveal to the transaction the key is used for
+pub fn destruction_use_tag(reveal: &[u8; 32], transaction_hash: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"SHIFT_KEY_USE_TAG");
+    hasher.update(reveal);
+    hasher.update(transaction_hash);
+    hasher.finalize().into()
+}
+
+/// `proof_data` for a CryptographicCommitment proof: the reveal opening the key's
+/// commitment, then its use tag for `transaction_hash`
+pub fn commitment_proof_data(reveal: &[u8; 32], transaction_hash: &[u8; 32]) -> [u8; PROOF_DATA_LEN] {
+    let mut proof_data = [0u8; PROOF_DATA_LEN];
+    proof_data[..32].copy_from_slice(reveal);
+    proof_data[32..64].copy_from_slice(&destruction_use_tag(reveal, transaction_hash));
+    proof_data
+}
+
+/// Known-answer vectors, shared with tests/shift-key-proofs.ts so the TypeScript SDK
+/// is checked against the same values
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC_KEY: [u8; 32] = [0x01; 32];
+    const TRANSACTION_HASH: [u8; 32] = [0x02; 32];
+    const NONCE: [u8; 32] = [0x03; 32];
+    const DEVICE_ID: [u8; 32] = [0x04; 32];
+    const MERKLE_ROOT: [u8; 32] = [0x05; 32];
+    const REVEAL: [u8; 32] = [0x06; 32];
+
+    fn hex32(hex: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn destruction_message_vector() {
+        assert_eq!(
+            destruction_message(&PUBLIC_KEY, &TRANSACTION_HASH, &NONCE),
+            hex32("4efca7d86d1f6ff8ac12f9ab17854230d3c1c5b70e5354c75790cb328eac024d")
+        );
+    }
+
+    #[test]
+    fn key_batch_message_vector() {
+        assert_eq!(
+            key_batch_message(&DEVICE_ID, &MERKLE_ROOT, 6, 4),
+            hex32("2b1f0a4808d741f934cf656a7b3c92871e686b8fa69baa0bcb90cdce173a43da")
+        );
+    }
+
+    #[test]
+    fn destruction_commitment_vector() {
+        assert_eq!(
+            destruction_commitment(&PUBLIC_KEY, &REVEAL),
+            hex32("08b9fcbce0fe803f68cbebfc025a5145268a273618625c4e29e83becf4e2cc24")
+        );
+    }
+
+    #[test]
+    fn destruction_use_tag_vector() {
+        assert_eq!(
+            destruction_use_tag(&REVEAL, &TRANSACTION_HASH),
+            hex32("7f46ce0d91d26c92f29110a06ba430ca7fc298a7bf9e16bef07015245d83315b")
+        );
+    }
+
+    #[test]
+    fn commitment_proof_data_carries_the_reveal_and_use_tag() {
+        let proof_data = commitment_proof_data(&REVEAL, &TRANSACTION_HASH);
+        assert_eq!(proof_data[..32], REVEAL);
+        assert_eq!(proof_data[32..64], destruction_use_tag(&REVEAL, &TRANSACTION_HASH));
+        assert!(proof_data[64..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn wots_vectors() {
+        let (secret_seed, public_seed, message) = ([0x11; 32], [0x22; 32], [0x33; 32]);
+
+        assert_eq!(
+            wots::public_key(&secret_seed, &public_seed, 5),
+            hex32("0c2d1ee20bf09ebfd6e25e2547f66bcf52e00ceeb27c5843f13e2d4021bf94be")
+        );
+
+        // SHA-256 over the 35 concatenated chain values
+        let mut hasher = Sha256::new();
+        for value in wots::sign(&secret_seed, &public_seed, 5, &message) {
+            hasher.update(value);
+        }
+        assert_eq!(
+            <[u8; 32]>::from(hasher.finalize()),
+            hex32("a240bb8a296a26895c3deed33fecfab5486c0d9765b96de2f4809dce0f611c5c")
+        );
+    }
+}
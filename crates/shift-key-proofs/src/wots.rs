@@ -0,0 +1,203 @@
+//! WOTS+ one-time signatures over SHA-256, for pools whose keys are hash-based.
+//! Parameters are n = 16 bytes and w = 16: 32 message chains plus 3 checksum chains,
+//! so a signature is 560 bytes. Verifying one takes about 260 hashes on average, which
+//! keeps on-chain verification well inside a transaction's compute budget.
+//! Chains are domain-separated by the pool's public seed, the key index, the chain
+//! index and the step, so chain values cannot be reused across keys or positions.
+//!
+//! Devices derive every key in a pool from one secret seed; shift-encumbrance only
+//! needs `public_key_from_signature` to check a signature against a committed key.
+
+use crate::hashv;
+
+/// Bytes per chain value
+pub const N: usize = 16;
+/// Winternitz parameter; each chain encodes one 4-bit digit of the message digest
+pub const W: usize = 16;
+/// Chains carrying the message digest, high nibble of each byte first
+pub const LEN1: usize = 2 * N;
+/// Chains carrying the checksum (at most 32 * 15 = 480, three base-16 digits)
+pub const LEN2: usize = 3;
+/// Total chains in a key
+pub const LEN: usize = LEN1 + LEN2;
+
+pub type WotsSignature = [[u8; N]; LEN];
+
+/// Advance `value` from position `start` along chain `chain` by `steps` steps.
+/// Callers keep `start + steps` within the chain length `W - 1`.
+fn chain(
+    public_seed: &[u8; 32],
+    key_index: u32,
+    chain: u8,
+    start: u8,
+    steps: u8,
+    value: &[u8; N],
+) -> [u8; N] {
+    debug_assert!((start as usize + steps as usize) < W);
+
+    let mut value = *value;
+    for step in start as usize..start as usize + steps as usize {
+        let hash = hashv(&[
+            b"SHIFT_WOTS_CHAIN",
+            public_seed,
+            &key_index.to_le_bytes(),
+            &[chain, step as u8],
+            &value,
+        ]);
+        value.copy_from_slice(&hash[..N]);
+    }
+    value
+}
+
+/// Start of chain `chain` for the key at `key_index`, derived from the device's secret seed
+fn secret_chain_start(secret_seed: &[u8; 32], key_index: u32, chain: u8) -> [u8; N] {
+    let hash = hashv(&[b"SHIFT_WOTS_SECRET", secret_seed, &key_index.to_le_bytes(), &[chain]]);
+    hash[..N].try_into().unwrap()
+}
+
+/// Digest of the signed message, bound to the key it is signed with
+pub fn message_digest(public_seed: &[u8; 32], key_index: u32, message: &[u8; 32]) -> [u8; N] {
+    let hash = hashv(&[b"SHIFT_WOTS_MESSAGE", public_seed, &key_index.to_le_bytes(), message]);
+    hash[..N].try_into().unwrap()
+}
+
+/// Base-w digits of a digest followed by its checksum, most significant digit first
+pub fn chain_lengths(digest: &[u8; N]) -> [u8; LEN] {
+    let mut lengths = [0u8; LEN];
+    for (digits, byte) in lengths[..LEN1].chunks_exact_mut(2).zip(digest) {
+        digits[0] = byte >> 4;
+        digits[1] = byte & 0x0f;
+    }
+
+    let checksum: u16 = lengths[..LEN1].iter().map(|digit| (W - 1) as u16 - *digit as u16).sum();
+    for (i, digit) in lengths[LEN1..].iter_mut().enumerate() {
+        *digit = (checksum >> (4 * (LEN2 - 1 - i)) & 0x0f) as u8;
+    }
+    lengths
+}
+
+/// Compress chain ends into the 32-byte public key committed to in the pool's Merkle tree
+pub fn compress_public_key(public_seed: &[u8; 32], chain_ends: &[[u8; N]; LEN]) -> [u8; 32] {
+    let mut parts: [&[u8]; 2 + LEN] = [&[]; 2 + LEN];
+    parts[0] = b"SHIFT_WOTS_PUBLIC_KEY";
+    parts[1] = public_seed;
+    for (part, chain_end) in parts[2..].iter_mut().zip(chain_ends) {
+        *part = chain_end;
+    }
+    hashv(&parts)
+}
+
+/// Compressed public key of the key at `key_index`, as committed in the pool's Merkle tree
+pub fn public_key(secret_seed: &[u8; 32], public_seed: &[u8; 32], key_index: u32) -> [u8; 32] {
+    let mut chain_ends = [[0u8; N]; LEN];
+    for (i, chain_end) in chain_ends.iter_mut().enumerate() {
+        let start = secret_chain_start(secret_seed, key_index, i as u8);
+        *chain_end = chain(public_seed, key_index, i as u8, 0, (W - 1) as u8, &start);
+    }
+
+    compress_public_key(public_seed, &chain_ends)
+}
+
+/// Sign `message` with the key at `key_index`. Each key must sign only once: a second
+/// signature reveals enough chain values to forge others, and is proof of a double-sign.
+pub fn sign(secret_seed: &[u8; 32], public_seed: &[u8; 32], key_index: u32, message: &[u8; 32]) -> WotsSignature {
+    let lengths = chain_lengths(&message_digest(public_seed, key_index, message));
+
+    let mut signature = [[0u8; N]; LEN];
+    for (i, value) in signature.iter_mut().enumerate() {
+        let start = secret_chain_start(secret_seed, key_index, i as u8);
+        *value = chain(public_seed, key_index, i as u8, 0, lengths[i], &start);
+    }
+    signature
+}
+
+/// Recover the compressed public key that `signature` signs `message` under.
+/// The signature is valid if this equals the key committed for `key_index`.
+pub fn public_key_from_signature(
+    public_seed: &[u8; 32],
+    key_index: u32,
+    message: &[u8; 32],
+    signature: &WotsSignature,
+) -> [u8; 32] {
+    let lengths = chain_lengths(&message_digest(public_seed, key_index, message));
+
+    let mut chain_ends = [[0u8; N]; LEN];
+    for (i, chain_end) in chain_ends.iter_mut().enumerate() {
+        let start = lengths[i];
+        *chain_end = chain(public_seed, key_index, i as u8, start, (W - 1) as u8 - start, &signature[i]);
+    }
+
+    compress_public_key(public_seed, &chain_ends)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET_SEED: [u8; 32] = [0x11; 32];
+    const PUBLIC_SEED: [u8; 32] = [0x22; 32];
+    const KEY_INDEX: u32 = 5;
+    const MESSAGE: [u8; 32] = [0x33; 32];
+
+    #[test]
+    fn valid_signature_recovers_public_key() {
+        let signature = sign(&SECRET_SEED, &PUBLIC_SEED, KEY_INDEX, &MESSAGE);
+
+        assert_eq!(
+            public_key_from_signature(&PUBLIC_SEED, KEY_INDEX, &MESSAGE, &signature),
+            public_key(&SECRET_SEED, &PUBLIC_SEED, KEY_INDEX)
+        );
+    }
+
+    #[test]
+    fn wrong_message_is_rejected() {
+        let signature = sign(&SECRET_SEED, &PUBLIC_SEED, KEY_INDEX, &MESSAGE);
+
+        assert_ne!(
+            public_key_from_signature(&PUBLIC_SEED, KEY_INDEX, &[0x34; 32], &signature),
+            public_key(&SECRET_SEED, &PUBLIC_SEED, KEY_INDEX)
+        );
+    }
+
+    #[test]
+    fn wrong_key_index_is_rejected() {
+        let signature = sign(&SECRET_SEED, &PUBLIC_SEED, KEY_INDEX, &MESSAGE);
+
+        assert_ne!(
+            public_key_from_signature(&PUBLIC_SEED, KEY_INDEX + 1, &MESSAGE, &signature),
+            public_key(&SECRET_SEED, &PUBLIC_SEED, KEY_INDEX + 1)
+        );
+        assert_ne!(
+            public_key_from_signature(&PUBLIC_SEED, KEY_INDEX + 1, &MESSAGE, &signature),
+            public_key(&SECRET_SEED, &PUBLIC_SEED, KEY_INDEX)
+        );
+    }
+
+    #[test]
+    fn tampered_checksum_chain_is_rejected() {
+        let mut signature = sign(&SECRET_SEED, &PUBLIC_SEED, KEY_INDEX, &MESSAGE);
+        signature[LEN1][0] ^= 1;
+
+        assert_ne!(
+            public_key_from_signature(&PUBLIC_SEED, KEY_INDEX, &MESSAGE, &signature),
+            public_key(&SECRET_SEED, &PUBLIC_SEED, KEY_INDEX)
+        );
+    }
+
+    #[test]
+    fn chain_lengths_append_checksum() {
+        // 32 zero digits: checksum 32 * 15 = 480 = 0x1e0
+        assert_eq!(chain_lengths(&[0u8; N])[LEN1..], [0x1, 0xe, 0x0]);
+        assert_eq!(chain_lengths(&[0xff; N])[LEN1..], [0, 0, 0]);
+    }
+
+    #[test]
+    fn chain_lengths_split_bytes_high_nibble_first() {
+        let mut digest = [0u8; N];
+        digest[0] = 0xa5;
+        let lengths = chain_lengths(&digest);
+        assert_eq!(lengths[..3], [0xa, 0x5, 0x0]);
+        // (15 - 10) + (15 - 5) + 30 * 15 = 465 = 0x1d1
+        assert_eq!(lengths[LEN1..], [0x1, 0xd, 0x1]);
+    }
+}
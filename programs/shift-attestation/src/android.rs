@@ -0,0 +1,426 @@
+//! Android Key Attestation: the KeyDescription extension (OID 1.3.6.1.4.1.11129.2.1.17)
+//! carried by the leaf certificate of a keystore attestation chain.
+
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::{SecurityLevel, VerifiedBootState};
+
+/// DER encoding of OID 1.3.6.1.4.1.11129.2.1.17
+pub const KEY_DESCRIPTION_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xd6, 0x79, 0x02, 0x01, 0x11];
+/// DER encoding of OID 1.3.101.112 (Ed25519)
+pub const ED25519_OID: &[u8] = &[0x2b, 0x65, 0x70];
+/// DER encoding of OID 1.2.840.10045.2.1 (id-ecPublicKey)
+pub const EC_PUBLIC_KEY_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+/// DER encoding of OID 1.2.840.10045.3.1.7 (prime256v1)
+pub const P256_CURVE_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+const TAG_BOOLEAN: u32 = 0x01;
+const TAG_INTEGER: u32 = 0x02;
+const TAG_OCTET_STRING: u32 = 0x04;
+const TAG_ENUMERATED: u32 = 0x0a;
+const TAG_SEQUENCE: u32 = 0x10;
+const TAG_ROOT_OF_TRUST: u32 = 704; // [704] EXPLICIT in AuthorizationList
+
+const CLASS_UNIVERSAL: u8 = 0x00;
+const CLASS_CONTEXT: u8 = 0x80;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RootOfTrust {
+    pub verified_boot_key: Vec<u8>,
+    pub device_locked: bool,
+    pub verified_boot_state: VerifiedBootState,
+    pub verified_boot_hash: Option<Vec<u8>>, // Present from attestation version 3
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyDescription {
+    pub attestation_version: u32,
+    pub attestation_security_level: SecurityLevel,
+    pub keymint_version: u32,
+    pub keymint_security_level: SecurityLevel,
+    pub attestation_challenge: Vec<u8>,
+    pub root_of_trust: Option<RootOfTrust>, // Taken from the hardware-enforced list
+}
+
+/// Attested key, for the algorithms the chain can verify signatures from.
+/// StrongBox only supports P-256, so Ed25519 keys come from the TEE.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttestedKey {
+    Ed25519([u8; 32]),
+    EcdsaP256([u8; 33]), // SEC1 compressed point
+}
+
+/// What a keystore attestation certificate says about its key and device
+#[derive(Clone, Debug, PartialEq)]
+pub struct AndroidAttestation {
+    pub key_description: KeyDescription,
+    pub attested_key: Option<AttestedKey>, // None for other key types
+}
+
+/// Parse a DER leaf certificate and its key attestation extension
+pub fn parse_attestation_certificate(certificate: &[u8]) -> Option<AndroidAttestation> {
+    let (_, certificate) = X509Certificate::from_der(certificate).ok()?;
+
+    let extension = certificate
+        .extensions()
+        .iter()
+        .find(|extension| extension.oid.as_bytes() == KEY_DESCRIPTION_OID)?;
+    let key_description = parse_key_description(extension.value)?;
+
+    Some(AndroidAttestation {
+        key_description,
+        attested_key: subject_key(&certificate),
+    })
+}
+
+/// A certificate's subject public key, if it is Ed25519 or P-256
+pub fn subject_key(certificate: &X509Certificate) -> Option<AttestedKey> {
+    let public_key = certificate.public_key();
+    let key_data = public_key.subject_public_key.data.as_ref();
+    match public_key.algorithm.algorithm.as_bytes() {
+        ED25519_OID => key_data.try_into().ok().map(AttestedKey::Ed25519),
+        EC_PUBLIC_KEY_OID
+            if public_key.algorithm.parameters.as_ref().map(|curve| curve.data) == Some(P256_CURVE_OID) =>
+        {
+            compress_p256_point(key_data).map(AttestedKey::EcdsaP256)
+        }
+        _ => None,
+    }
+}
+
+/// SEC1 compressed form of an uncompressed or compressed P-256 point
+fn compress_p256_point(point: &[u8]) -> Option<[u8; 33]> {
+    let mut compressed = [0u8; 33];
+    match point {
+        [0x04, coordinates @ ..] if coordinates.len() == 64 => {
+            compressed[0] = 0x02 | (coordinates[63] & 1);
+            compressed[1..].copy_from_slice(&coordinates[..32]);
+        }
+        [0x02 | 0x03, ..] if point.len() == 33 => compressed.copy_from_slice(point),
+        _ => return None,
+    }
+    Some(compressed)
+}
+
+/// Parse the KeyDescription SEQUENCE carried in the extension value
+pub fn parse_key_description(data: &[u8]) -> Option<KeyDescription> {
+    let mut outer = DerReader::new(data);
+    let mut fields = DerReader::new(outer.expect(CLASS_UNIVERSAL, TAG_SEQUENCE)?);
+
+    let attestation_version = parse_small_integer(fields.expect(CLASS_UNIVERSAL, TAG_INTEGER)?)?;
+    let attestation_security_level = parse_security_level(fields.expect(CLASS_UNIVERSAL, TAG_ENUMERATED)?)?;
+    let keymint_version = parse_small_integer(fields.expect(CLASS_UNIVERSAL, TAG_INTEGER)?)?;
+    let keymint_security_level = parse_security_level(fields.expect(CLASS_UNIVERSAL, TAG_ENUMERATED)?)?;
+    let attestation_challenge = fields.expect(CLASS_UNIVERSAL, TAG_OCTET_STRING)?.to_vec();
+    let _unique_id = fields.expect(CLASS_UNIVERSAL, TAG_OCTET_STRING)?;
+    let _software_enforced = fields.expect(CLASS_UNIVERSAL, TAG_SEQUENCE)?;
+    let hardware_enforced = fields.expect(CLASS_UNIVERSAL, TAG_SEQUENCE)?;
+
+    Some(KeyDescription {
+        attestation_version,
+        attestation_security_level,
+        keymint_version,
+        keymint_security_level,
+        attestation_challenge,
+        root_of_trust: find_root_of_trust(hardware_enforced)?,
+    })
+}
+
+/// Find the RootOfTrust in an AuthorizationList. `Some(None)` means it is absent.
+fn find_root_of_trust(authorization_list: &[u8]) -> Option<Option<RootOfTrust>> {
+    let mut entries = DerReader::new(authorization_list);
+
+    while !entries.is_done() {
+        let (class, tag, content) = entries.read()?;
+        if class == CLASS_CONTEXT && tag == TAG_ROOT_OF_TRUST {
+            return parse_root_of_trust(content).map(Some);
+        }
+    }
+
+    Some(None)
+}
+
+fn parse_root_of_trust(explicit: &[u8]) -> Option<RootOfTrust> {
+    let mut outer = DerReader::new(explicit);
+    let mut fields = DerReader::new(outer.expect(CLASS_UNIVERSAL, TAG_SEQUENCE)?);
+
+    let verified_boot_key = fields.expect(CLASS_UNIVERSAL, TAG_OCTET_STRING)?.to_vec();
+    let device_locked = fields.expect(CLASS_UNIVERSAL, TAG_BOOLEAN)?.first().copied()? != 0;
+    let verified_boot_state = match parse_small_integer(fields.expect(CLASS_UNIVERSAL, TAG_ENUMERATED)?)? {
+        0 => VerifiedBootState::Verified,
+        1 => VerifiedBootState::SelfSigned,
+        2 => VerifiedBootState::Unverified,
+        3 => VerifiedBootState::Failed,
+        _ => return None,
+    };
+    let verified_boot_hash = if fields.is_done() {
+        None
+    } else {
+        Some(fields.expect(CLASS_UNIVERSAL, TAG_OCTET_STRING)?.to_vec())
+    };
+
+    Some(RootOfTrust {
+        verified_boot_key,
+        device_locked,
+        verified_boot_state,
+        verified_boot_hash,
+    })
+}
+
+fn parse_security_level(content: &[u8]) -> Option<SecurityLevel> {
+    match parse_small_integer(content)? {
+        0 => Some(SecurityLevel::Software),
+        1 => Some(SecurityLevel::TrustedEnvironment),
+        2 => Some(SecurityLevel::StrongBox),
+        _ => None,
+    }
+}
+
+/// Non-negative INTEGER/ENUMERATED content that fits in a u32
+fn parse_small_integer(content: &[u8]) -> Option<u32> {
+    let content = match content {
+        [0x00, rest @ ..] if !rest.is_empty() => rest,
+        [first, ..] if first & 0x80 != 0 => return None,
+        _ => content,
+    };
+    if content.is_empty() || content.len() > 4 {
+        return None;
+    }
+    Some(content.iter().fold(0u32, |value, byte| (value << 8) | *byte as u32))
+}
+
+/// Reader over a run of DER TLVs
+struct DerReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> DerReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        DerReader { data, pos: 0 }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    /// Read one TLV, returning (class, tag number, content)
+    fn read(&mut self) -> Option<(u8, u32, &'a [u8])> {
+        let identifier = self.byte()?;
+        let class = identifier & 0xc0;
+
+        let mut tag = (identifier & 0x1f) as u32;
+        if tag == 0x1f {
+            // High tag number form, base-128 big-endian
+            tag = 0;
+            loop {
+                let byte = self.byte()?;
+                tag = tag.checked_mul(128)? | (byte & 0x7f) as u32;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+
+        let first = self.byte()?;
+        let len = if first & 0x80 == 0 {
+            first as usize
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 {
+                return None;
+            }
+            let mut len = 0usize;
+            for _ in 0..count {
+                len = (len << 8) | self.byte()? as usize;
+            }
+            len
+        };
+
+        let content = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some((class, tag, content))
+    }
+
+    /// Read one TLV and require its class and tag number
+    fn expect(&mut self, class: u8, tag: u32) -> Option<&'a [u8]> {
+        let (read_class, read_tag, content) = self.read()?;
+        (read_class == class && read_tag == tag).then_some(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generated by testdata/generate_android.py; self-signed, not from a real device
+    const TEE_ED25519_V3: &[u8] = include_bytes!("../testdata/android_tee_ed25519_v3.der");
+    const STRONGBOX_P256_V100: &[u8] = include_bytes!("../testdata/android_strongbox_p256_v100.der");
+    const TEE_RSA_V4: &[u8] = include_bytes!("../testdata/android_tee_rsa_v4.der");
+
+    const CHALLENGE: [u8; 32] = [0x42; 32];
+
+    fn tlv(identifier: &[u8], content: &[u8]) -> Vec<u8> {
+        let mut encoded = identifier.to_vec();
+        if content.len() < 0x80 {
+            encoded.push(content.len() as u8);
+        } else {
+            encoded.extend_from_slice(&[0x82, (content.len() >> 8) as u8, content.len() as u8]);
+        }
+        encoded.extend_from_slice(content);
+        encoded
+    }
+
+    fn sequence(parts: &[Vec<u8>]) -> Vec<u8> {
+        tlv(&[0x30], &parts.concat())
+    }
+
+    fn root_of_trust(boot_hash: Option<&[u8]>) -> Vec<u8> {
+        let mut fields = vec![tlv(&[0x04], &[0xb0; 32]), tlv(&[0x01], &[0xff]), tlv(&[0x0a], &[0x00])];
+        if let Some(boot_hash) = boot_hash {
+            fields.push(tlv(&[0x04], boot_hash));
+        }
+        tlv(&[0xbf, 0x85, 0x40], &sequence(&fields))
+    }
+
+    fn key_description(version: u8, security_level: u8, hardware_enforced: &[Vec<u8>]) -> Vec<u8> {
+        sequence(&[
+            tlv(&[0x02], &[version]),
+            tlv(&[0x0a], &[security_level]),
+            tlv(&[0x02], &[version]),
+            tlv(&[0x0a], &[security_level]),
+            tlv(&[0x04], &CHALLENGE),
+            tlv(&[0x04], &[]),
+            sequence(&[]),
+            sequence(hardware_enforced),
+        ])
+    }
+
+    #[test]
+    fn parses_version_3_tee_key_description() {
+        let description = parse_key_description(&key_description(3, 1, &[root_of_trust(Some(&[0xb1; 32]))])).unwrap();
+
+        assert_eq!(description.attestation_version, 3);
+        assert_eq!(description.attestation_security_level, SecurityLevel::TrustedEnvironment);
+        assert_eq!(description.attestation_challenge, CHALLENGE);
+
+        let root_of_trust = description.root_of_trust.unwrap();
+        assert!(root_of_trust.device_locked);
+        assert_eq!(root_of_trust.verified_boot_state, VerifiedBootState::Verified);
+        assert_eq!(root_of_trust.verified_boot_hash, Some(vec![0xb1; 32]));
+    }
+
+    #[test]
+    fn parses_version_4_strongbox_key_description() {
+        let description = parse_key_description(&key_description(4, 2, &[root_of_trust(Some(&[0xb1; 32]))])).unwrap();
+
+        assert_eq!(description.attestation_version, 4);
+        assert_eq!(description.attestation_security_level, SecurityLevel::StrongBox);
+        assert_eq!(description.keymint_security_level, SecurityLevel::StrongBox);
+    }
+
+    #[test]
+    fn version_2_root_of_trust_has_no_boot_hash() {
+        let description = parse_key_description(&key_description(2, 1, &[root_of_trust(None)])).unwrap();
+
+        assert_eq!(description.root_of_trust.unwrap().verified_boot_hash, None);
+    }
+
+    #[test]
+    fn missing_root_of_trust_is_reported_as_absent() {
+        // An unrelated hardware-enforced tag ([1] purpose) and no [704]
+        let purpose = tlv(&[0xa1], &tlv(&[0x31], &tlv(&[0x02], &[0x02])));
+        let description = parse_key_description(&key_description(3, 1, &[purpose])).unwrap();
+
+        assert_eq!(description.root_of_trust, None);
+    }
+
+    #[test]
+    fn rejects_truncated_key_description() {
+        let encoded = key_description(3, 1, &[root_of_trust(Some(&[0xb1; 32]))]);
+
+        for len in [0, 1, 2, encoded.len() / 2, encoded.len() - 1] {
+            assert_eq!(parse_key_description(&encoded[..len]), None, "truncated to {len} bytes");
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_lengths() {
+        let mut encoded = key_description(3, 1, &[root_of_trust(Some(&[0xb1; 32]))]);
+        // Outer SEQUENCE uses the two-byte long form; claim one byte more than is present
+        assert_eq!(encoded[1], 0x82);
+        let len = u16::from_be_bytes([encoded[2], encoded[3]]) + 1;
+        encoded[2..4].copy_from_slice(&len.to_be_bytes());
+        assert_eq!(parse_key_description(&encoded), None);
+
+        // Lengths wider than four bytes are refused outright
+        assert_eq!(parse_key_description(&[0x30, 0x85, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00]), None);
+    }
+
+    #[test]
+    fn rejects_unknown_security_level_and_negative_versions() {
+        assert_eq!(parse_key_description(&key_description(3, 3, &[])), None);
+        assert_eq!(parse_key_description(&key_description(0x80, 1, &[])), None);
+    }
+
+    #[test]
+    fn parses_tee_certificate_with_ed25519_key() {
+        let attestation = parse_attestation_certificate(TEE_ED25519_V3).unwrap();
+
+        assert_eq!(attestation.key_description.attestation_version, 3);
+        assert_eq!(
+            attestation.key_description.attestation_security_level,
+            SecurityLevel::TrustedEnvironment
+        );
+        assert_eq!(
+            attestation.attested_key,
+            Some(AttestedKey::Ed25519(
+                hex32("8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c")
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_strongbox_certificate_with_p256_key() {
+        let attestation = parse_attestation_certificate(STRONGBOX_P256_V100).unwrap();
+
+        assert_eq!(attestation.key_description.attestation_version, 100);
+        assert_eq!(attestation.key_description.attestation_security_level, SecurityLevel::StrongBox);
+        assert_eq!(
+            attestation.key_description.root_of_trust.unwrap().verified_boot_hash,
+            Some(vec![0xb1; 32])
+        );
+
+        let mut compressed = [0u8; 33];
+        compressed[0] = 0x03;
+        compressed[1..].copy_from_slice(&hex32("7cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978"));
+        assert_eq!(attestation.attested_key, Some(AttestedKey::EcdsaP256(compressed)));
+    }
+
+    #[test]
+    fn certificate_with_other_key_type_has_no_attested_key() {
+        let attestation = parse_attestation_certificate(TEE_RSA_V4).unwrap();
+
+        assert_eq!(attestation.key_description.attestation_version, 4);
+        assert_eq!(attestation.attested_key, None);
+    }
+
+    #[test]
+    fn rejects_truncated_certificate() {
+        assert_eq!(parse_attestation_certificate(&TEE_ED25519_V3[..TEE_ED25519_V3.len() - 1]), None);
+    }
+
+    fn hex32(hex: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        bytes
+    }
+}
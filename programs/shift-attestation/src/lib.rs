@@ -1,19 +1,67 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    sysvar::{instructions as instructions_sysvar, slot_hashes},
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use sha2::{Digest, Sha256};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+pub mod android;
+pub mod tpm;
 
 declare_id!("ATT3ST111111111111111111111111111111111111");
 
+/// Longest window during which a rotated-out manufacturer key is still honoured
+pub const MAX_KEY_ROTATION_OVERLAP: i64 = 86400 * 30; // 30 days
+
+/// AttestationQuote version recorded for quotes translated from TPM 2.0
+pub const TPM2_QUOTE_VERSION: u32 = 2;
+
+/// secp256r1 signature verification precompile (SIMD-0075)
+pub const SECP256R1_PROGRAM_ID: Pubkey = anchor_lang::solana_program::pubkey!("Secp256r1SigVerify1111111111111111111111111");
+
+/// Protocol-wide cap on attestation validity until governance changes it
+pub const DEFAULT_MAX_VALIDITY_PERIOD: i64 = 86400 * 30; // 30 days
+
+/// Highest cap governance can set on attestation validity
+pub const MAX_VALIDITY_PERIOD_LIMIT: i64 = 86400 * 365; // 1 year
+
+/// Most attesters a manufacturer can require to endorse a single quote
+pub const MAX_ENDORSEMENT_THRESHOLD: u8 = 8;
+
+/// Largest governance signer set
+pub const MAX_GOVERNANCE_SIGNERS: usize = 10;
+
+/// Longest manufacturer name, in bytes
+pub const MAX_MANUFACTURER_NAME_LEN: usize = 50;
+
 #[program]
 pub mod shift_attestation {
     use super::*;
 
-    /// Initialize the attestation system
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    /// Initialize the attestation system under an M-of-N governance signer set.
+    /// Adding a manufacturer only takes effect `manufacturer_timelock` seconds after it is proposed.
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+        manufacturer_timelock: i64,
+    ) -> Result<()> {
+        validate_signer_set(&signers, threshold)?;
+        require!(manufacturer_timelock >= 0, AttestationError::InvalidGovernanceAction);
+
         let attestation_authority = &mut ctx.accounts.attestation_authority;
-        attestation_authority.authority = ctx.accounts.authority.key();
-        attestation_authority.trusted_manufacturers = Vec::new();
+        attestation_authority.signers = signers;
+        attestation_authority.threshold = threshold;
+        attestation_authority.signer_set_version = 0;
+        attestation_authority.manufacturer_timelock = manufacturer_timelock;
+        attestation_authority.proposal_count = 0;
+        attestation_authority.total_manufacturers = 0;
+        attestation_authority.manufacturer_generations = 0;
         attestation_authority.total_attestations = 0;
+        attestation_authority.max_validity_period = DEFAULT_MAX_VALIDITY_PERIOD;
+        attestation_authority.fraud_reporter = None;
         attestation_authority.bump = ctx.bumps.attestation_authority;
         
         msg!("Shift Attestation system initialized");
@@ -26,19 +74,44 @@ pub mod shift_attestation {
         manufacturer_id: [u8; 32],
         name: String,
         public_key: [u8; 32],
+        hardware_type: HardwareType,
     ) -> Result<()> {
+        ctx.accounts.proposal.consume(
+            &ctx.accounts.attestation_authority,
+            &GovernanceAction::AddManufacturer {
+                manufacturer_id,
+                name: name.clone(),
+                public_key,
+                hardware_type: hardware_type.clone(),
+            },
+        )?;
+
         let manufacturer_account = &mut ctx.accounts.manufacturer_account;
         let attestation_authority = &mut ctx.accounts.attestation_authority;
 
         manufacturer_account.manufacturer_id = manufacturer_id;
         manufacturer_account.name = name;
         manufacturer_account.public_key = public_key;
+        manufacturer_account.policy = AttestationPolicy {
+            validity_period: hardware_type
+                .default_validity_period()
+                .min(attestation_authority.max_validity_period),
+            grace_period: 0,
+            require_cosign: false,
+            endorsement_threshold: 1,
+        };
+        manufacturer_account.hardware_type = hardware_type;
+        manufacturer_account.previous_public_key = None;
+        manufacturer_account.previous_key_valid_until = 0;
         manufacturer_account.is_active = true;
+        manufacturer_account.deactivated_at = None;
         manufacturer_account.devices_attested = 0;
         manufacturer_account.created_at = Clock::get()?.unix_timestamp;
+        manufacturer_account.generation = attestation_authority.manufacturer_generations;
         manufacturer_account.bump = ctx.bumps.manufacturer_account;
 
-        attestation_authority.trusted_manufacturers.push(manufacturer_id);
+        attestation_authority.total_manufacturers += 1;
+        attestation_authority.manufacturer_generations += 1;
 
         msg!("Trusted manufacturer added: {:?}", manufacturer_id);
         Ok(())
@@ -49,6 +122,7 @@ pub mod shift_attestation {
         ctx: Context<CreateAttestation>,
         device_id: [u8; 32],
         manufacturer_id: [u8; 32],
+        device_owner: Pubkey,
         attestation_quote: AttestationQuote,
         device_certificate: [u8; 1024], // Device certificate from manufacturer
     ) -> Result<()> {
@@ -75,16 +149,25 @@ pub mod shift_attestation {
             AttestationError::InvalidDeviceCertificate
         );
 
+        require_measurements_not_deprecated(
+            &ctx.accounts.measurement_deprecation,
+            &attestation_quote.measurements,
+        )?;
+
         // Create attestation record
-        attestation_record.device_id = device_id;
-        attestation_record.manufacturer_id = manufacturer_id;
-        attestation_record.attestation_quote = attestation_quote;
-        attestation_record.device_certificate = device_certificate;
-        attestation_record.status = AttestationStatus::Valid;
-        attestation_record.created_at = Clock::get()?.unix_timestamp;
-        attestation_record.expires_at = Clock::get()?.unix_timestamp + 86400 * 30; // 30 days
+        attestation_record.initialize(
+            device_id,
+            device_owner,
+            manufacturer,
+            attestation_authority.max_validity_period,
+            attestation_quote,
+            device_certificate,
+            QuoteFormat::Shift,
+        )?;
+        attestation_record.attested_by = ctx.accounts.attester.key();
         attestation_record.bump = ctx.bumps.attestation_record;
 
+        attestation_record.series = ctx.accounts.device_series.next(device_id, ctx.bumps.device_series);
         manufacturer.devices_attested += 1;
         attestation_authority.total_attestations += 1;
 
@@ -92,12 +175,252 @@ pub mod shift_attestation {
         Ok(())
     }
 
-    /// Verify an existing attestation
+    /// Create an attestation from a TPM 2.0 quote: a raw TPMS_ATTEST structure and the
+    /// TPMT_SIGNATURE over it. ECDSA P-256 attestation keys (the usual TPM attestation key)
+    /// are checked through the secp256r1 program over the TPMS_ATTEST bytes; EdDSA keys
+    /// through the Ed25519 program over their SHA-256 digest.
+    ///
+    /// The quote must answer the on-chain challenge for `device_id` at `challenge_slot`,
+    /// and `device_certificate` must certify the attestation key under the manufacturer's
+    /// Ed25519 key, checked by an Ed25519 program instruction over its TBSCertificate.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_attestation_tpm(
+        ctx: Context<CreateAttestationTpm>,
+        device_id: [u8; 32],
+        manufacturer_id: [u8; 32],
+        device_owner: Pubkey,
+        attestation_key: TpmAttestationKey,
+        tpms_attest: Vec<u8>,
+        tpmt_signature: Vec<u8>,
+        challenge_slot: u64,
+        device_certificate: [u8; 1024],
+    ) -> Result<()> {
+        let attestation_record = &mut ctx.accounts.attestation_record;
+        let manufacturer = &mut ctx.accounts.manufacturer_account;
+        let attestation_authority = &mut ctx.accounts.attestation_authority;
+
+        require!(manufacturer.is_active, AttestationError::UntrustedManufacturer);
+        require!(
+            manufacturer.manufacturer_id == manufacturer_id,
+            AttestationError::ManufacturerMismatch
+        );
+        require!(
+            manufacturer.hardware_type == HardwareType::TrustedExecutionEnvironment,
+            AttestationError::HardwareTypeMismatch
+        );
+
+        let instructions = &ctx.accounts.instructions;
+        require!(
+            certificate_certifies_key(instructions, &device_certificate, &manufacturer.public_key, &attestation_key)?,
+            AttestationError::InvalidDeviceCertificate
+        );
+
+        let (attestation_quote, device_key_algorithm) = verify_tpm_quote(
+            instructions,
+            &ctx.accounts.slot_hashes,
+            &device_id,
+            challenge_slot,
+            attestation_key,
+            &tpms_attest,
+            &tpmt_signature,
+        )?;
+        require_measurements_not_deprecated(
+            &ctx.accounts.measurement_deprecation,
+            &attestation_quote.measurements,
+        )?;
+
+        attestation_record.initialize(
+            device_id,
+            device_owner,
+            manufacturer,
+            attestation_authority.max_validity_period,
+            attestation_quote,
+            device_certificate,
+            QuoteFormat::Tpm2,
+        )?;
+        attestation_record.device_key_algorithm = device_key_algorithm;
+        attestation_record.attested_by = ctx.accounts.attester.key();
+        attestation_record.bump = ctx.bumps.attestation_record;
+
+        attestation_record.series = ctx.accounts.device_series.next(device_id, ctx.bumps.device_series);
+        manufacturer.devices_attested += 1;
+        attestation_authority.total_attestations += 1;
+
+        msg!("TPM device attestation created: {:?}", device_id);
+        Ok(())
+    }
+
+    /// Refresh a TPM attestation with a new quote from the attestation key certified at
+    /// creation, answering a fresh on-chain challenge. The new PCR selection and digest
+    /// go through the same deprecation check as at creation.
+    pub fn refresh_attestation_tpm(
+        ctx: Context<RefreshHardwareAttestation>,
+        device_id: [u8; 32],
+        attestation_key: TpmAttestationKey,
+        tpms_attest: Vec<u8>,
+        tpmt_signature: Vec<u8>,
+        challenge_slot: u64,
+    ) -> Result<()> {
+        let attestation_record = &mut ctx.accounts.attestation_record;
+        let manufacturer = &ctx.accounts.manufacturer_account;
+
+        attestation_record.authorize_refresh(
+            &device_id,
+            manufacturer,
+            &ctx.accounts.refresher.key(),
+            ctx.accounts.attester_account.is_some(),
+        )?;
+        require!(
+            attestation_record.quote_format == QuoteFormat::Tpm2,
+            AttestationError::QuoteFormatMismatch
+        );
+
+        let (new_attestation_quote, _) = verify_tpm_quote(
+            &ctx.accounts.instructions,
+            &ctx.accounts.slot_hashes,
+            &device_id,
+            challenge_slot,
+            attestation_key,
+            &tpms_attest,
+            &tpmt_signature,
+        )?;
+        require!(
+            new_attestation_quote.public_key == attestation_record.device_key,
+            AttestationError::DeviceKeyMismatch
+        );
+        require_measurements_not_deprecated(
+            &ctx.accounts.measurement_deprecation,
+            &new_attestation_quote.measurements,
+        )?;
+
+        attestation_record.replace_quote(
+            &mut ctx.accounts.history_entry,
+            ctx.bumps.history_entry,
+            new_attestation_quote,
+            manufacturer,
+            ctx.accounts.attestation_authority.max_validity_period,
+        )?;
+
+        msg!("TPM device attestation refreshed: {:?}", device_id);
+        Ok(())
+    }
+
+    /// Create an attestation from an Android Key Attestation leaf certificate. The key
+    /// description extension supplies the security level, verified boot state and challenge;
+    /// the certificate chain up to the Google root is checked off-chain by the attester.
+    pub fn create_attestation_android(
+        ctx: Context<CreateAttestation>,
+        device_id: [u8; 32],
+        manufacturer_id: [u8; 32],
+        device_owner: Pubkey,
+        attestation_certificate: Vec<u8>,
+        challenge_nonce: [u8; 32],
+    ) -> Result<()> {
+        let attestation_record = &mut ctx.accounts.attestation_record;
+        let manufacturer = &mut ctx.accounts.manufacturer_account;
+        let attestation_authority = &mut ctx.accounts.attestation_authority;
+
+        require!(manufacturer.is_active, AttestationError::UntrustedManufacturer);
+        require!(
+            manufacturer.manufacturer_id == manufacturer_id,
+            AttestationError::ManufacturerMismatch
+        );
+        require!(
+            manufacturer.hardware_type == HardwareType::TrustedExecutionEnvironment,
+            AttestationError::HardwareTypeMismatch
+        );
+
+        let evidence = verify_android_attestation(&attestation_certificate, &challenge_nonce)?;
+        require_measurements_not_deprecated(
+            &ctx.accounts.measurement_deprecation,
+            &evidence.attestation_quote.measurements,
+        )?;
+
+        attestation_record.initialize(
+            device_id,
+            device_owner,
+            manufacturer,
+            attestation_authority.max_validity_period,
+            evidence.attestation_quote,
+            evidence.device_certificate,
+            QuoteFormat::AndroidKeyAttestation,
+        )?;
+        attestation_record.device_key_algorithm = evidence.device_key_algorithm;
+        attestation_record.security_level = evidence.security_level;
+        attestation_record.verified_boot_state = Some(evidence.verified_boot_state);
+        attestation_record.attested_by = ctx.accounts.attester.key();
+        attestation_record.bump = ctx.bumps.attestation_record;
+
+        attestation_record.series = ctx.accounts.device_series.next(device_id, ctx.bumps.device_series);
+        manufacturer.devices_attested += 1;
+        attestation_authority.total_attestations += 1;
+
+        msg!("Android device attestation created: {:?}", device_id);
+        Ok(())
+    }
+
+    /// Refresh an Android attestation from a new attestation certificate for the same key,
+    /// answering a fresh on-chain challenge. The security level, verified boot state and
+    /// boot measurement are taken from the new certificate and checked as on creation.
+    pub fn refresh_attestation_android(
+        ctx: Context<RefreshHardwareAttestation>,
+        device_id: [u8; 32],
+        attestation_certificate: Vec<u8>,
+        challenge_slot: u64,
+    ) -> Result<()> {
+        let attestation_record = &mut ctx.accounts.attestation_record;
+        let manufacturer = &ctx.accounts.manufacturer_account;
+
+        attestation_record.authorize_refresh(
+            &device_id,
+            manufacturer,
+            &ctx.accounts.refresher.key(),
+            ctx.accounts.attester_account.is_some(),
+        )?;
+        require!(
+            attestation_record.quote_format == QuoteFormat::AndroidKeyAttestation,
+            AttestationError::QuoteFormatMismatch
+        );
+
+        let challenge = device_challenge(&ctx.accounts.slot_hashes, &device_id, challenge_slot)?;
+        let evidence = verify_android_attestation(&attestation_certificate, &challenge)?;
+        require!(
+            evidence.attestation_quote.public_key == attestation_record.device_key
+                && evidence.device_key_algorithm == attestation_record.device_key_algorithm,
+            AttestationError::DeviceKeyMismatch
+        );
+        require_measurements_not_deprecated(
+            &ctx.accounts.measurement_deprecation,
+            &evidence.attestation_quote.measurements,
+        )?;
+
+        attestation_record.replace_quote(
+            &mut ctx.accounts.history_entry,
+            ctx.bumps.history_entry,
+            evidence.attestation_quote,
+            manufacturer,
+            ctx.accounts.attestation_authority.max_validity_period,
+        )?;
+        // The record follows the certificate now in use, including for CRL checks
+        attestation_record.certificate_serial_hash = certificate_serial_hash(&evidence.device_certificate);
+        attestation_record.device_certificate = evidence.device_certificate;
+        attestation_record.security_level = evidence.security_level;
+        attestation_record.verified_boot_state = Some(evidence.verified_boot_state);
+
+        msg!("Android device attestation refreshed: {:?}", device_id);
+        Ok(())
+    }
+
+    /// Verify an existing attestation and summarize what it vouches for, so callers can
+    /// apply their own policy (e.g. limits by security level). Within the manufacturer's
+    /// grace period after expiry the attestation still verifies but reports that it
+    /// needs a refresh.
     pub fn verify_attestation(
         ctx: Context<VerifyAttestation>,
         device_id: [u8; 32],
-    ) -> Result<bool> {
+    ) -> Result<AttestationSummary> {
         let attestation_record = &ctx.accounts.attestation_record;
+        let manufacturer = &ctx.accounts.manufacturer_account;
         let current_time = Clock::get()?.unix_timestamp;
 
         // Check if attestation exists and is valid
@@ -112,12 +435,59 @@ pub mod shift_attestation {
         );
 
         require!(
-            current_time < attestation_record.expires_at,
+            current_time < attestation_record.expires_at + manufacturer.policy.grace_period,
             AttestationError::AttestationExpired
         );
 
-        msg!("Device attestation verified: {:?}", device_id);
-        Ok(true)
+        // Records whose firmware measurements have been deprecated are no longer trusted
+        require!(
+            ctx.accounts.measurement_deprecation.data_is_empty(),
+            AttestationError::MeasurementSetDeprecated
+        );
+
+        // Certificates the manufacturer has revoked by serial number are no longer trusted
+        if let Some(serial_hash) = attestation_record.certificate_serial_hash {
+            let crl_bucket = &ctx.accounts.crl_bucket;
+            if !crl_bucket.data_is_empty() {
+                let crl_bucket = CrlBucket::try_deserialize(&mut &crl_bucket.try_borrow_data()?[..])?;
+                require!(
+                    !crl_bucket.contains(&serial_hash),
+                    AttestationError::CertificateRevoked
+                );
+            }
+        }
+
+        // Attestations from deactivated manufacturers or retired keys are no longer trusted,
+        // nor are those made before the manufacturer was removed and added again
+        require!(manufacturer.is_active, AttestationError::UntrustedManufacturer);
+        require!(
+            attestation_record.is_from(manufacturer),
+            AttestationError::StaleManufacturer
+        );
+        require!(
+            manufacturer.is_key_trusted(&attestation_record.manufacturer_key, current_time),
+            AttestationError::ManufacturerKeyRetired
+        );
+
+        let validity = if current_time < attestation_record.expires_at {
+            AttestationValidity::Valid
+        } else {
+            AttestationValidity::NeedsRefresh
+        };
+
+        msg!("Device attestation verified: {:?} ({:?})", device_id, validity);
+        Ok(AttestationSummary {
+            device_id,
+            device_key: attestation_record.device_key,
+            device_key_algorithm: attestation_record.device_key_algorithm,
+            manufacturer_id: attestation_record.manufacturer_id,
+            hardware_type: manufacturer.hardware_type.clone(),
+            security_level: attestation_record.security_level.clone(),
+            verified_boot_state: attestation_record.verified_boot_state.clone(),
+            measurement_set_id: attestation_record.measurement_set_id,
+            expires_at: attestation_record.expires_at,
+            validity,
+        })
     }
 
     /// Revoke an attestation (in case of compromise)
@@ -126,6 +496,11 @@ pub mod shift_attestation {
         device_id: [u8; 32],
         reason: RevocationReason,
     ) -> Result<()> {
+        ctx.accounts.proposal.consume(
+            &ctx.accounts.attestation_authority,
+            &GovernanceAction::RevokeAttestation { device_id, reason: reason.clone() },
+        )?;
+
         let attestation_record = &mut ctx.accounts.attestation_record;
 
         require!(
@@ -133,15 +508,83 @@ pub mod shift_attestation {
             AttestationError::DeviceIdMismatch
         );
 
-        attestation_record.status = AttestationStatus::Revoked;
-        attestation_record.revocation_reason = Some(reason);
-        attestation_record.revoked_at = Some(Clock::get()?.unix_timestamp);
+        attestation_record.revoke(reason, Clock::get()?.unix_timestamp);
 
         msg!("Device attestation revoked: {:?}", device_id);
         Ok(())
     }
 
-    /// Update attestation (refresh before expiry)
+    /// Undo a revocation made for a reversible reason (e.g. a lost device that was found)
+    pub fn reinstate_attestation(
+        ctx: Context<ReinstateAttestation>,
+        device_id: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.proposal.consume(
+            &ctx.accounts.attestation_authority,
+            &GovernanceAction::ReinstateAttestation { device_id },
+        )?;
+
+        let attestation_record = &mut ctx.accounts.attestation_record;
+
+        require!(
+            attestation_record.status == AttestationStatus::Revoked,
+            AttestationError::AttestationNotRevoked
+        );
+        require!(
+            attestation_record
+                .revocation_reason
+                .as_ref()
+                .is_some_and(|reason| reason.is_reversible()),
+            AttestationError::RevocationNotReversible
+        );
+
+        // Expiry is unchanged, so a long-lost device may still need a refresh.
+        // Pending or flagged records go back to that status, not to Valid.
+        attestation_record.status = attestation_record
+            .status_before_revocation
+            .take()
+            .unwrap_or(AttestationStatus::Valid);
+        attestation_record.revocation_reason = None;
+        attestation_record.revoked_at = None;
+
+        msg!("Device attestation reinstated: {:?}", device_id);
+        Ok(())
+    }
+
+    /// Close a revoked attestation so the device can be attested from scratch.
+    /// History entries are kept; a new record starts a new history series.
+    pub fn close_attestation(
+        ctx: Context<CloseAttestation>,
+        device_id: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.proposal.consume(
+            &ctx.accounts.attestation_authority,
+            &GovernanceAction::CloseAttestation { device_id },
+        )?;
+
+        require!(
+            ctx.accounts.attestation_record.status == AttestationStatus::Revoked,
+            AttestationError::AttestationNotRevoked
+        );
+
+        // Keep the counts in step. A manufacturer that has since been removed, or added
+        // again under the same ID, did not count this record.
+        let manufacturer_info = &ctx.accounts.manufacturer_account;
+        if !manufacturer_info.data_is_empty() {
+            let mut data = manufacturer_info.try_borrow_mut_data()?;
+            let mut manufacturer = ManufacturerAccount::try_deserialize(&mut &data[..])?;
+            if ctx.accounts.attestation_record.is_from(&manufacturer) {
+                manufacturer.devices_attested -= 1;
+                manufacturer.try_serialize(&mut &mut data[..])?;
+            }
+        }
+        ctx.accounts.attestation_authority.total_attestations -= 1;
+
+        msg!("Device attestation closed: {:?}", device_id);
+        Ok(())
+    }
+
+    /// Update attestation (refresh before expiry, or revive an expired one)
     pub fn refresh_attestation(
         ctx: Context<RefreshAttestation>,
         device_id: [u8; 32],
@@ -150,14 +593,34 @@ pub mod shift_attestation {
         let attestation_record = &mut ctx.accounts.attestation_record;
         let manufacturer = &ctx.accounts.manufacturer_account;
 
+        attestation_record.authorize_refresh(
+            &device_id,
+            manufacturer,
+            &ctx.accounts.refresher.key(),
+            ctx.accounts.attester_account.is_some(),
+        )?;
+
+        // TPM and Android records are refreshed from new hardware evidence, so their PCRs
+        // or verified boot state are checked again
         require!(
-            attestation_record.device_id == device_id,
-            AttestationError::DeviceIdMismatch
+            attestation_record.quote_format == QuoteFormat::Shift,
+            AttestationError::QuoteFormatMismatch
         );
 
+        // The new quote must come from the same device key, proven by a signature from it
         require!(
-            attestation_record.status == AttestationStatus::Valid,
-            AttestationError::InvalidAttestation
+            new_attestation_quote.public_key == attestation_record.device_key,
+            AttestationError::DeviceKeyMismatch
+        );
+
+        let quote_hash = calculate_quote_hash(&device_id, &new_attestation_quote);
+        require!(
+            attestation_record.verify_device_signature(
+                &ctx.accounts.instructions,
+                &quote_hash,
+                &new_attestation_quote.signature,
+            )?,
+            AttestationError::InvalidQuoteSignature
         );
 
         // Verify new attestation quote
@@ -166,152 +629,1489 @@ pub mod shift_attestation {
             AttestationError::InvalidAttestationQuote
         );
 
-        attestation_record.attestation_quote = new_attestation_quote;
-        attestation_record.expires_at = Clock::get()?.unix_timestamp + 86400 * 30; // Extend 30 days
+        // Devices cannot move onto firmware that has been deprecated
+        require_measurements_not_deprecated(
+            &ctx.accounts.measurement_deprecation,
+            &new_attestation_quote.measurements,
+        )?;
+
+        attestation_record.replace_quote(
+            &mut ctx.accounts.history_entry,
+            ctx.bumps.history_entry,
+            new_attestation_quote,
+            manufacturer,
+            ctx.accounts.attestation_authority.max_validity_period,
+        )?;
 
         msg!("Device attestation refreshed: {:?}", device_id);
         Ok(())
     }
-}
 
-// Context structs
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + AttestationAuthority::LEN,
-        seeds = [b"attestation_authority"],
-        bump
-    )]
-    pub attestation_authority: Account<'info, AttestationAuthority>,
-    
-    pub system_program: Program<'info, System>,
-}
+    /// Deprecate a set of firmware measurements (e.g. a vulnerable firmware version).
+    /// Every attestation carrying these measurements fails verification from now on.
+    pub fn deprecate_measurement_set(
+        ctx: Context<DeprecateMeasurementSet>,
+        measurement_set_id: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.proposal.consume(
+            &ctx.accounts.attestation_authority,
+            &GovernanceAction::DeprecateMeasurementSet { measurement_set_id },
+        )?;
 
-#[derive(Accounts)]
-#[instruction(manufacturer_id: [u8; 32])]
-pub struct AddTrustedManufacturer<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + ManufacturerAccount::LEN,
-        seeds = [b"manufacturer", manufacturer_id.as_ref()],
-        bump
-    )]
-    pub manufacturer_account: Account<'info, ManufacturerAccount>,
-    
-    #[account(
-        mut,
-        seeds = [b"attestation_authority"],
-        bump = attestation_authority.bump,
-        constraint = attestation_authority.authority == authority.key()
-    )]
-    pub attestation_authority: Account<'info, AttestationAuthority>,
-    
-    pub system_program: Program<'info, System>,
-}
+        let deprecated_measurement_set = &mut ctx.accounts.deprecated_measurement_set;
 
-#[derive(Accounts)]
-#[instruction(device_id: [u8; 32], manufacturer_id: [u8; 32])]
-pub struct CreateAttestation<'info> {
-    #[account(mut)]
-    pub attester: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = attester,
-        space = 8 + AttestationRecord::LEN,
-        seeds = [b"attestation", device_id.as_ref()],
-        bump
-    )]
-    pub attestation_record: Account<'info, AttestationRecord>,
-    
-    #[account(
-        mut,
-        seeds = [b"manufacturer", manufacturer_id.as_ref()],
-        bump = manufacturer_account.bump
-    )]
-    pub manufacturer_account: Account<'info, ManufacturerAccount>,
-    
-    #[account(
-        mut,
-        seeds = [b"attestation_authority"],
-        bump = attestation_authority.bump
-    )]
-    pub attestation_authority: Account<'info, AttestationAuthority>,
-    
-    pub system_program: Program<'info, System>,
-}
+        deprecated_measurement_set.measurement_set_id = measurement_set_id;
+        deprecated_measurement_set.deprecated_by = ctx.accounts.proposal.proposer;
+        deprecated_measurement_set.deprecated_at = Clock::get()?.unix_timestamp;
+        deprecated_measurement_set.bump = ctx.bumps.deprecated_measurement_set;
 
-#[derive(Accounts)]
-#[instruction(device_id: [u8; 32])]
-pub struct VerifyAttestation<'info> {
-    #[account(
-        seeds = [b"attestation", device_id.as_ref()],
-        bump = attestation_record.bump
-    )]
-    pub attestation_record: Account<'info, AttestationRecord>,
-}
+        msg!("Measurement set deprecated: {:?}", measurement_set_id);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(device_id: [u8; 32])]
-pub struct RevokeAttestation<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    #[account(
-        mut,
-        seeds = [b"attestation", device_id.as_ref()],
-        bump = attestation_record.bump
-    )]
-    pub attestation_record: Account<'info, AttestationRecord>,
-    
-    #[account(
-        seeds = [b"attestation_authority"],
-        bump = attestation_authority.bump,
-        constraint = attestation_authority.authority == authority.key()
-    )]
-    pub attestation_authority: Account<'info, AttestationAuthority>,
-}
+    /// Create an empty CRL bucket for a manufacturer. Revoked serials are sharded into
+    /// 256 buckets by the first byte of their SHA-256 hash.
+    pub fn initialize_crl_bucket(
+        ctx: Context<InitializeCrlBucket>,
+        manufacturer_id: [u8; 32],
+        bucket: u8,
+    ) -> Result<()> {
+        let crl_bucket = &mut ctx.accounts.crl_bucket;
+
+        crl_bucket.manufacturer_id = manufacturer_id;
+        crl_bucket.bucket = bucket;
+        crl_bucket.crl_number = 0;
+        crl_bucket.serial_hashes = Vec::new();
+        crl_bucket.updated_at = Clock::get()?.unix_timestamp;
+        crl_bucket.bump = ctx.bumps.crl_bucket;
+
+        msg!("CRL bucket {} initialized for manufacturer {:?}", bucket, manufacturer_id);
+        Ok(())
+    }
+
+    /// Import revoked certificate serials from a manufacturer CRL. `crl_number` must
+    /// increase with every update so stale CRLs cannot be replayed.
+    pub fn add_crl_entries(
+        ctx: Context<AddCrlEntries>,
+        manufacturer_id: [u8; 32],
+        bucket: u8,
+        crl_number: u64,
+        serial_hashes: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let crl_bucket = &mut ctx.accounts.crl_bucket;
+
+        require!(crl_number > crl_bucket.crl_number, AttestationError::StaleCrl);
+        require!(
+            serial_hashes.iter().all(|serial_hash| serial_hash[0] == bucket),
+            AttestationError::CrlBucketMismatch
+        );
+
+        // Keep the list sorted so verification can binary search it
+        for serial_hash in serial_hashes {
+            if let Err(position) = crl_bucket.serial_hashes.binary_search(&serial_hash) {
+                crl_bucket.serial_hashes.insert(position, serial_hash);
+            }
+        }
+        crl_bucket.crl_number = crl_number;
+        crl_bucket.updated_at = Clock::get()?.unix_timestamp;
+
+        msg!(
+            "CRL {} imported for manufacturer {:?} bucket {} ({} revoked serials)",
+            crl_number,
+            manufacturer_id,
+            bucket,
+            crl_bucket.serial_hashes.len()
+        );
+        Ok(())
+    }
+
+    /// Deactivate a manufacturer; all attestations it issued stop verifying
+    pub fn deactivate_manufacturer(
+        ctx: Context<UpdateManufacturer>,
+        manufacturer_id: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.proposal.consume(
+            &ctx.accounts.attestation_authority,
+            &GovernanceAction::DeactivateManufacturer { manufacturer_id },
+        )?;
+
+        let manufacturer_account = &mut ctx.accounts.manufacturer_account;
+
+        require!(manufacturer_account.is_active, AttestationError::UntrustedManufacturer);
+
+        manufacturer_account.is_active = false;
+        manufacturer_account.deactivated_at = Some(Clock::get()?.unix_timestamp);
+
+        msg!("Manufacturer deactivated: {:?}", manufacturer_id);
+        Ok(())
+    }
+
+    /// Rotate a manufacturer's signing key. Attestations made under the previous key
+    /// keep verifying until the overlap period ends and must be refreshed before then.
+    pub fn rotate_manufacturer_key(
+        ctx: Context<UpdateManufacturer>,
+        manufacturer_id: [u8; 32],
+        new_public_key: [u8; 32],
+        overlap_period: i64,
+    ) -> Result<()> {
+        ctx.accounts.proposal.consume(
+            &ctx.accounts.attestation_authority,
+            &GovernanceAction::RotateManufacturerKey {
+                manufacturer_id,
+                new_public_key,
+                overlap_period,
+            },
+        )?;
+
+        let manufacturer_account = &mut ctx.accounts.manufacturer_account;
+
+        require!(manufacturer_account.is_active, AttestationError::UntrustedManufacturer);
+        require!(
+            new_public_key != manufacturer_account.public_key,
+            AttestationError::InvalidManufacturerKey
+        );
+
+        manufacturer_account.previous_public_key = Some(manufacturer_account.public_key);
+        manufacturer_account.previous_key_valid_until = Clock::get()?.unix_timestamp + overlap_period;
+        manufacturer_account.public_key = new_public_key;
+
+        msg!("Manufacturer key rotated: {:?}", manufacturer_id);
+        Ok(())
+    }
+
+    /// Remove a deactivated manufacturer and reclaim its account
+    pub fn remove_manufacturer(
+        ctx: Context<RemoveManufacturer>,
+        manufacturer_id: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.proposal.consume(
+            &ctx.accounts.attestation_authority,
+            &GovernanceAction::RemoveManufacturer { manufacturer_id },
+        )?;
+
+        let attestation_authority = &mut ctx.accounts.attestation_authority;
+
+        require!(
+            !ctx.accounts.manufacturer_account.is_active,
+            AttestationError::ManufacturerStillActive
+        );
+
+        attestation_authority.total_manufacturers -= 1;
+
+        msg!("Manufacturer removed: {:?}", manufacturer_id);
+        Ok(())
+    }
+
+    /// Register an attester key allowed to create attestations for a manufacturer
+    pub fn add_attester(
+        ctx: Context<AddAttester>,
+        manufacturer_id: [u8; 32],
+        attester: Pubkey,
+    ) -> Result<()> {
+        let attester_account = &mut ctx.accounts.attester_account;
+
+        require!(
+            ctx.accounts.manufacturer_account.is_active,
+            AttestationError::UntrustedManufacturer
+        );
+
+        attester_account.manufacturer_id = manufacturer_id;
+        attester_account.attester = attester;
+        attester_account.added_at = Clock::get()?.unix_timestamp;
+        attester_account.bump = ctx.bumps.attester_account;
+
+        msg!("Attester added for manufacturer {:?}: {}", manufacturer_id, attester);
+        Ok(())
+    }
+
+    /// Remove a registered attester key
+    pub fn remove_attester(
+        _ctx: Context<RemoveAttester>,
+        manufacturer_id: [u8; 32],
+        attester: Pubkey,
+    ) -> Result<()> {
+        msg!("Attester removed for manufacturer {:?}: {}", manufacturer_id, attester);
+        Ok(())
+    }
+
+    /// Set the protocol-wide maximum attestation validity period
+    pub fn set_max_validity_period(
+        ctx: Context<SetMaxValidityPeriod>,
+        max_validity_period: i64,
+    ) -> Result<()> {
+        ctx.accounts.proposal.consume(
+            &ctx.accounts.attestation_authority,
+            &GovernanceAction::SetMaxValidityPeriod { max_validity_period },
+        )?;
+
+        ctx.accounts.attestation_authority.max_validity_period = max_validity_period;
+
+        msg!("Maximum attestation validity set to {} seconds", max_validity_period);
+        Ok(())
+    }
+
+    /// Set how long a manufacturer's attestations stay valid, and the grace period after
+    /// expiry during which they still verify but need a refresh
+    pub fn set_attestation_policy(
+        ctx: Context<SetAttestationPolicy>,
+        manufacturer_id: [u8; 32],
+        policy: AttestationPolicy,
+    ) -> Result<()> {
+        let max_validity_period = ctx.accounts.attestation_authority.max_validity_period;
+
+        require!(
+            policy.validity_period > 0 && policy.validity_period <= max_validity_period,
+            AttestationError::InvalidValidityPolicy
+        );
+        require!(
+            policy.grace_period >= 0 && policy.grace_period <= policy.validity_period,
+            AttestationError::InvalidValidityPolicy
+        );
+        require!(
+            (1..=MAX_ENDORSEMENT_THRESHOLD).contains(&policy.endorsement_threshold),
+            AttestationError::InvalidValidityPolicy
+        );
+
+        ctx.accounts.manufacturer_account.policy = policy;
+
+        msg!("Attestation policy updated for manufacturer {:?}", manufacturer_id);
+        Ok(())
+    }
+
+    /// Mark an attestation past its expiry (and grace period) as Expired.
+    /// Permissionless so that anyone can keep on-chain status fields current.
+    pub fn expire_attestation(
+        ctx: Context<ExpireAttestation>,
+        device_id: [u8; 32],
+    ) -> Result<()> {
+        let attestation_record = &mut ctx.accounts.attestation_record;
+        let manufacturer = &ctx.accounts.manufacturer_account;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        require!(
+            attestation_record.status == AttestationStatus::Valid,
+            AttestationError::InvalidAttestation
+        );
+        require!(
+            current_time >= attestation_record.expires_at + manufacturer.policy.grace_period,
+            AttestationError::AttestationNotExpired
+        );
+
+        attestation_record.status = AttestationStatus::Expired;
+
+        emit!(AttestationExpired {
+            device_id,
+            expires_at: attestation_record.expires_at,
+            expired_at: current_time,
+        });
+
+        msg!("Device attestation expired: {:?}", device_id);
+        Ok(())
+    }
+
+    /// Close a pending attestation that was never confirmed or endorsed before its expiry
+    /// (and grace period), refunding rent to the attester that created it. Permissionless,
+    /// so abandoned records do not block the device from being attested again.
+    pub fn close_stale_attestation(
+        ctx: Context<CloseStaleAttestation>,
+        device_id: [u8; 32],
+    ) -> Result<()> {
+        let attestation_record = &ctx.accounts.attestation_record;
+        let manufacturer = &mut ctx.accounts.manufacturer_account;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        require!(
+            attestation_record.status == AttestationStatus::Pending,
+            AttestationError::AttestationNotPending
+        );
+        require!(
+            current_time >= attestation_record.expires_at + manufacturer.policy.grace_period,
+            AttestationError::AttestationNotExpired
+        );
+
+        if attestation_record.is_from(manufacturer) {
+            manufacturer.devices_attested -= 1;
+        }
+        ctx.accounts.attestation_authority.total_attestations -= 1;
+
+        emit!(StaleAttestationClosed {
+            device_id,
+            attested_by: attestation_record.attested_by,
+            closed_at: current_time,
+        });
+
+        msg!("Stale pending attestation closed: {:?}", device_id);
+        Ok(())
+    }
+
+    /// Flag an attestation whose device has been proven to misuse its keys.
+    /// Only the governance-approved fraud reporter (the encumbrance program's
+    /// authority PDA) may flag; governance decides whether to revoke.
+    pub fn flag_attestation(
+        ctx: Context<FlagAttestation>,
+        device_id: [u8; 32],
+    ) -> Result<()> {
+        let attestation_record = &mut ctx.accounts.attestation_record;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        // A revoked record is already untrusted and keeps its revocation reason
+        if attestation_record.status != AttestationStatus::Revoked {
+            attestation_record.status = AttestationStatus::Flagged;
+        }
+
+        emit!(AttestationFlagged {
+            device_id,
+            reporter: ctx.accounts.fraud_reporter.key(),
+            flagged_at: current_time,
+        });
+
+        msg!("Device attestation flagged: {:?}", device_id);
+        Ok(())
+    }
+
+    /// Manufacturer co-signature that moves a pending attestation to Valid
+    pub fn confirm_attestation(
+        ctx: Context<ConfirmAttestation>,
+        device_id: [u8; 32],
+    ) -> Result<()> {
+        let attestation_record = &mut ctx.accounts.attestation_record;
+        let manufacturer = &ctx.accounts.manufacturer_account;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        require!(manufacturer.is_active, AttestationError::UntrustedManufacturer);
+        require!(
+            attestation_record.is_from(manufacturer),
+            AttestationError::StaleManufacturer
+        );
+        require!(
+            attestation_record.status == AttestationStatus::Pending,
+            AttestationError::AttestationNotPending
+        );
+
+        attestation_record.cosigned = true;
+        let max_validity_period = ctx.accounts.attestation_authority.max_validity_period;
+        if attestation_record.activate_if_endorsed(manufacturer, max_validity_period, current_time) {
+            emit!(AttestationConfirmed {
+                device_id,
+                manufacturer_id: attestation_record.manufacturer_id,
+                confirmed_at: current_time,
+            });
+        }
+
+        msg!("Device attestation confirmed: {:?}", device_id);
+        Ok(())
+    }
+
+    /// Endorse a pending attestation's quote as an additional registered attester.
+    /// The record becomes Valid once the manufacturer's endorsement threshold is met.
+    pub fn endorse_attestation(
+        ctx: Context<EndorseAttestation>,
+        device_id: [u8; 32],
+        quote_hash: [u8; 32],
+    ) -> Result<()> {
+        let attestation_record = &mut ctx.accounts.attestation_record;
+        let pending_endorsement = &mut ctx.accounts.pending_endorsement;
+        let manufacturer = &ctx.accounts.manufacturer_account;
+        let endorser = ctx.accounts.endorser.key();
+        let current_time = Clock::get()?.unix_timestamp;
+
+        require!(manufacturer.is_active, AttestationError::UntrustedManufacturer);
+        require!(
+            attestation_record.is_from(manufacturer),
+            AttestationError::StaleManufacturer
+        );
+        require!(
+            attestation_record.status == AttestationStatus::Pending,
+            AttestationError::AttestationNotPending
+        );
+
+        // Endorsers must vouch for the exact quote the record holds
+        require!(
+            quote_hash == calculate_quote_hash(&device_id, &attestation_record.attestation_quote),
+            AttestationError::EndorsementQuoteMismatch
+        );
+        require!(
+            endorser != attestation_record.attested_by
+                && !pending_endorsement.endorsers.contains(&endorser),
+            AttestationError::DuplicateEndorsement
+        );
+        require!(
+            pending_endorsement.endorsers.len() < PendingEndorsement::MAX_ENDORSERS,
+            AttestationError::TooManyEndorsements
+        );
+
+        if pending_endorsement.endorsers.is_empty() {
+            pending_endorsement.device_id = device_id;
+            pending_endorsement.bump = ctx.bumps.pending_endorsement;
+        }
+        pending_endorsement.quote_hash = quote_hash;
+        pending_endorsement.endorsers.push(endorser);
+        attestation_record.endorsements += 1;
+
+        let max_validity_period = ctx.accounts.attestation_authority.max_validity_period;
+        if attestation_record.activate_if_endorsed(manufacturer, max_validity_period, current_time) {
+            emit!(AttestationConfirmed {
+                device_id,
+                manufacturer_id: attestation_record.manufacturer_id,
+                confirmed_at: current_time,
+            });
+        }
+
+        msg!(
+            "Device attestation endorsed: {:?} ({}/{})",
+            device_id,
+            attestation_record.endorsements,
+            manufacturer.policy.endorsement_threshold
+        );
+        Ok(())
+    }
+
+    /// Propose a governance action. The proposer's approval is counted immediately.
+    pub fn propose(ctx: Context<Propose>, action: GovernanceAction) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let attestation_authority = &mut ctx.accounts.attestation_authority;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        action.validate()?;
+
+        // Onboarding a manufacturer waits out the timelock so a rogue proposal can be spotted
+        let delay = match action {
+            GovernanceAction::AddManufacturer { .. } => attestation_authority.manufacturer_timelock,
+            _ => 0,
+        };
+
+        proposal.proposal_id = attestation_authority.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action;
+        proposal.approvals = vec![ctx.accounts.proposer.key()];
+        proposal.signer_set_version = attestation_authority.signer_set_version;
+        proposal.created_at = current_time;
+        proposal.executable_at = current_time + delay;
+        proposal.executed = false;
+        proposal.bump = ctx.bumps.proposal;
+
+        attestation_authority.proposal_count += 1;
+
+        msg!("Governance proposal {} created", proposal.proposal_id);
+        Ok(())
+    }
+
+    /// Withdraw a proposal that has not been executed and refund its rent to the proposer.
+    /// The proposer can cancel at any time; any signer can clear a proposal left stale
+    /// by a signer set change.
+    pub fn cancel_proposal(ctx: Context<CancelProposal>, proposal_id: u64) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+
+        require!(
+            ctx.accounts.canceller.key() == proposal.proposer
+                || proposal.signer_set_version != ctx.accounts.attestation_authority.signer_set_version,
+            AttestationError::UnauthorizedCancellation
+        );
+
+        msg!("Governance proposal {} cancelled", proposal_id);
+        Ok(())
+    }
+
+    /// Approve a pending governance proposal
+    pub fn approve_proposal(ctx: Context<ApproveProposal>, proposal_id: u64) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let approver = ctx.accounts.approver.key();
+
+        require!(!proposal.executed, AttestationError::ProposalAlreadyExecuted);
+        require!(
+            proposal.signer_set_version == ctx.accounts.attestation_authority.signer_set_version,
+            AttestationError::ProposalStale
+        );
+        require!(!proposal.approvals.contains(&approver), AttestationError::AlreadyApproved);
+
+        proposal.approvals.push(approver);
+
+        msg!(
+            "Governance proposal {} approved ({}/{})",
+            proposal_id,
+            proposal.approvals.len(),
+            ctx.accounts.attestation_authority.threshold
+        );
+        Ok(())
+    }
+
+    /// Execute an approved change to the governance configuration itself
+    pub fn execute_governance_update(ctx: Context<ExecuteGovernanceUpdate>) -> Result<()> {
+        let attestation_authority = &mut ctx.accounts.attestation_authority;
+        let proposal = &mut ctx.accounts.proposal;
+
+        let action = proposal.action.clone();
+        proposal.consume(attestation_authority, &action)?;
+
+        match action {
+            GovernanceAction::SetSigners { signers, threshold } => {
+                attestation_authority.signers = signers;
+                attestation_authority.threshold = threshold;
+                // Approvals collected under the old signer set no longer count
+                attestation_authority.signer_set_version += 1;
+            }
+            GovernanceAction::SetManufacturerTimelock { manufacturer_timelock } => {
+                attestation_authority.manufacturer_timelock = manufacturer_timelock;
+            }
+            GovernanceAction::SetFraudReporter { fraud_reporter } => {
+                attestation_authority.fraud_reporter = fraud_reporter;
+            }
+            _ => return err!(AttestationError::InvalidGovernanceAction),
+        }
+
+        msg!("Governance proposal {} executed", proposal.proposal_id);
+        Ok(())
+    }
+}
+
+// Context structs
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AttestationAuthority::LEN,
+        seeds = [b"attestation_authority"],
+        bump
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(manufacturer_id: [u8; 32])]
+pub struct AddTrustedManufacturer<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = executor,
+        space = 8 + ManufacturerAccount::LEN,
+        seeds = [b"manufacturer", manufacturer_id.as_ref()],
+        bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&executor.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+    #[account(
+        mut,
+        close = executor,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32], manufacturer_id: [u8; 32])]
+pub struct CreateAttestation<'info> {
+    #[account(mut)]
+    pub attester: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = attester,
+        space = 8 + AttestationRecord::LEN,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    /// Numbers the device's records, so a re-created record gets fresh history and
+    /// endorsement accounts
+    #[account(
+        init_if_needed,
+        payer = attester,
+        space = 8 + DeviceSeries::LEN,
+        seeds = [b"device_series", device_id.as_ref()],
+        bump
+    )]
+    pub device_series: Account<'info, DeviceSeries>,
+    
+    #[account(
+        mut,
+        seeds = [b"manufacturer", manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    /// Only attesters registered by the manufacturer may create attestations
+    #[account(
+        seeds = [b"attester", manufacturer_id.as_ref(), attester.key().as_ref()],
+        bump = attester_account.bump
+    )]
+    pub attester_account: Account<'info, AttesterAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+
+    /// CHECK: Deprecation marker for the quote's measurement set, checked in the handler
+    pub measurement_deprecation: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32], manufacturer_id: [u8; 32])]
+pub struct CreateAttestationTpm<'info> {
+    #[account(mut)]
+    pub attester: Signer<'info>,
+
+    #[account(
+        init,
+        payer = attester,
+        space = 8 + AttestationRecord::LEN,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    /// Numbers the device's records, so a re-created record gets fresh history and
+    /// endorsement accounts
+    #[account(
+        init_if_needed,
+        payer = attester,
+        space = 8 + DeviceSeries::LEN,
+        seeds = [b"device_series", device_id.as_ref()],
+        bump
+    )]
+    pub device_series: Account<'info, DeviceSeries>,
+
+    #[account(
+        mut,
+        seeds = [b"manufacturer", manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        seeds = [b"attester", manufacturer_id.as_ref(), attester.key().as_ref()],
+        bump = attester_account.bump
+    )]
+    pub attester_account: Account<'info, AttesterAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+
+    /// CHECK: Deprecation marker for the quote's measurement set, checked in the handler
+    pub measurement_deprecation: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, used to find the Ed25519 signature check
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// CHECK: SlotHashes sysvar, source of the challenge the quote answers
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct VerifyAttestation<'info> {
+    #[account(
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    #[account(
+        seeds = [b"manufacturer", attestation_record.manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    /// CHECK: Deprecation marker for the record's measurement set; only its existence is checked
+    #[account(
+        seeds = [b"deprecated_measurements", attestation_record.measurement_set_id.as_ref()],
+        bump
+    )]
+    pub measurement_deprecation: UncheckedAccount<'info>,
+
+    /// CHECK: CRL bucket for the record's certificate serial; may not exist yet
+    #[account(
+        seeds = [b"crl", attestation_record.manufacturer_id.as_ref(), &[attestation_record.crl_bucket()]],
+        bump
+    )]
+    pub crl_bucket: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct RevokeAttestation<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+    
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&executor.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+    #[account(
+        mut,
+        close = executor,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct ReinstateAttestation<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&executor.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+    #[account(
+        mut,
+        close = executor,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct CloseAttestation<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        close = executor,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    /// CHECK: The record's manufacturer, which may since have been removed. Its count of
+    /// attested devices is updated in the handler if it still exists.
+    #[account(
+        mut,
+        seeds = [b"manufacturer", attestation_record.manufacturer_id.as_ref()],
+        bump
+    )]
+    pub manufacturer_account: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&executor.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+    #[account(
+        mut,
+        close = executor,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct RefreshAttestation<'info> {
+    #[account(mut)]
+    pub refresher: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    #[account(
+        init,
+        payer = refresher,
+        space = 8 + AttestationHistoryEntry::LEN,
+        seeds = [
+            b"attestation_history",
+            device_id.as_ref(),
+            &attestation_record.series.to_le_bytes(),
+            &attestation_record.history_length.to_le_bytes()
+        ],
+        bump
+    )]
+    pub history_entry: Account<'info, AttestationHistoryEntry>,
+    
+    #[account(
+        seeds = [b"manufacturer", attestation_record.manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+
+    /// Present when the refresher is an attester registered by the manufacturer
+    #[account(
+        seeds = [b"attester", attestation_record.manufacturer_id.as_ref(), refresher.key().as_ref()],
+        bump = attester_account.bump
+    )]
+    pub attester_account: Option<Account<'info, AttesterAccount>>,
+
+    /// CHECK: Deprecation marker for the quote's measurement set, checked in the handler
+    pub measurement_deprecation: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, used to find the Ed25519 signature check
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Refresh from hardware evidence answering an on-chain challenge
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct RefreshHardwareAttestation<'info> {
+    #[account(mut)]
+    pub refresher: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    #[account(
+        init,
+        payer = refresher,
+        space = 8 + AttestationHistoryEntry::LEN,
+        seeds = [
+            b"attestation_history",
+            device_id.as_ref(),
+            &attestation_record.series.to_le_bytes(),
+            &attestation_record.history_length.to_le_bytes()
+        ],
+        bump
+    )]
+    pub history_entry: Account<'info, AttestationHistoryEntry>,
+
+    #[account(
+        seeds = [b"manufacturer", attestation_record.manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+
+    /// Present when the refresher is an attester registered by the manufacturer
+    #[account(
+        seeds = [b"attester", attestation_record.manufacturer_id.as_ref(), refresher.key().as_ref()],
+        bump = attester_account.bump
+    )]
+    pub attester_account: Option<Account<'info, AttesterAccount>>,
+
+    /// CHECK: Deprecation marker for the quote's measurement set, checked in the handler
+    pub measurement_deprecation: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, used to find the quote's signature check
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// CHECK: SlotHashes sysvar, source of the challenge the quote answers
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(measurement_set_id: [u8; 32])]
+pub struct DeprecateMeasurementSet<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        init,
+        payer = executor,
+        space = 8 + DeprecatedMeasurementSet::LEN,
+        seeds = [b"deprecated_measurements", measurement_set_id.as_ref()],
+        bump
+    )]
+    pub deprecated_measurement_set: Account<'info, DeprecatedMeasurementSet>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&executor.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+    #[account(
+        mut,
+        close = executor,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(manufacturer_id: [u8; 32])]
+pub struct UpdateManufacturer<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"manufacturer", manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&executor.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+    #[account(
+        mut,
+        close = executor,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+#[instruction(manufacturer_id: [u8; 32])]
+pub struct RemoveManufacturer<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        close = executor,
+        seeds = [b"manufacturer", manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&executor.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+    #[account(
+        mut,
+        close = executor,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+#[instruction(manufacturer_id: [u8; 32], bucket: u8)]
+pub struct InitializeCrlBucket<'info> {
+    #[account(
+        mut,
+        constraint = manufacturer.key().to_bytes() == manufacturer_account.public_key
+            @ AttestationError::UnauthorizedManufacturer
+    )]
+    pub manufacturer: Signer<'info>,
+
+    #[account(
+        seeds = [b"manufacturer", manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        init,
+        payer = manufacturer,
+        space = CrlBucket::space(0),
+        seeds = [b"crl", manufacturer_id.as_ref(), &[bucket]],
+        bump
+    )]
+    pub crl_bucket: Account<'info, CrlBucket>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(manufacturer_id: [u8; 32], bucket: u8, crl_number: u64, serial_hashes: Vec<[u8; 32]>)]
+pub struct AddCrlEntries<'info> {
+    #[account(
+        mut,
+        constraint = manufacturer.key().to_bytes() == manufacturer_account.public_key
+            @ AttestationError::UnauthorizedManufacturer
+    )]
+    pub manufacturer: Signer<'info>,
+
+    #[account(
+        seeds = [b"manufacturer", manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"crl", manufacturer_id.as_ref(), &[bucket]],
+        bump = crl_bucket.bump,
+        realloc = CrlBucket::space(crl_bucket.serial_hashes.len() + serial_hashes.len()),
+        realloc::payer = manufacturer,
+        realloc::zero = false
+    )]
+    pub crl_bucket: Account<'info, CrlBucket>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(manufacturer_id: [u8; 32], attester: Pubkey)]
+pub struct AddAttester<'info> {
+    #[account(
+        mut,
+        constraint = manufacturer.key().to_bytes() == manufacturer_account.public_key
+            @ AttestationError::UnauthorizedManufacturer
+    )]
+    pub manufacturer: Signer<'info>,
+
+    #[account(
+        seeds = [b"manufacturer", manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        init,
+        payer = manufacturer,
+        space = 8 + AttesterAccount::LEN,
+        seeds = [b"attester", manufacturer_id.as_ref(), attester.as_ref()],
+        bump
+    )]
+    pub attester_account: Account<'info, AttesterAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(manufacturer_id: [u8; 32], attester: Pubkey)]
+pub struct RemoveAttester<'info> {
+    #[account(
+        mut,
+        constraint = manufacturer.key().to_bytes() == manufacturer_account.public_key
+            @ AttestationError::UnauthorizedManufacturer
+    )]
+    pub manufacturer: Signer<'info>,
+
+    #[account(
+        seeds = [b"manufacturer", manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        mut,
+        close = manufacturer,
+        seeds = [b"attester", manufacturer_id.as_ref(), attester.as_ref()],
+        bump = attester_account.bump
+    )]
+    pub attester_account: Account<'info, AttesterAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxValidityPeriod<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&executor.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+    #[account(
+        mut,
+        close = executor,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+#[instruction(manufacturer_id: [u8; 32])]
+pub struct SetAttestationPolicy<'info> {
+    #[account(
+        constraint = manufacturer.key().to_bytes() == manufacturer_account.public_key
+            @ AttestationError::UnauthorizedManufacturer
+    )]
+    pub manufacturer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"manufacturer", manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct ExpireAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    #[account(
+        seeds = [b"manufacturer", attestation_record.manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct CloseStaleAttestation<'info> {
+    #[account(
+        mut,
+        close = attester,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    #[account(
+        mut,
+        seeds = [b"manufacturer", attestation_record.manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+
+    /// CHECK: Attester that paid for the record; only receives its rent
+    #[account(mut, address = attestation_record.attested_by)]
+    pub attester: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct FlagAttestation<'info> {
+    pub fraud_reporter: Signer<'info>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.fraud_reporter == Some(fraud_reporter.key()) @ AttestationError::UnauthorizedFraudReporter
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct ConfirmAttestation<'info> {
+    #[account(
+        constraint = manufacturer.key().to_bytes() == manufacturer_account.public_key
+            @ AttestationError::UnauthorizedManufacturer
+    )]
+    pub manufacturer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    #[account(
+        seeds = [b"manufacturer", attestation_record.manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct EndorseAttestation<'info> {
+    #[account(mut)]
+    pub endorser: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    #[account(
+        init_if_needed,
+        payer = endorser,
+        space = 8 + PendingEndorsement::LEN,
+        seeds = [
+            b"endorsement",
+            device_id.as_ref(),
+            attestation_record.series.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub pending_endorsement: Account<'info, PendingEndorsement>,
+
+    #[account(
+        seeds = [b"manufacturer", attestation_record.manufacturer_id.as_ref()],
+        bump = manufacturer_account.bump
+    )]
+    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+
+    /// Only attesters registered by the manufacturer may endorse
+    #[account(
+        seeds = [b"attester", attestation_record.manufacturer_id.as_ref(), endorser.key().as_ref()],
+        bump = attester_account.bump
+    )]
+    pub attester_account: Account<'info, AttesterAccount>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Propose<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::LEN,
+        seeds = [b"proposal", attestation_authority.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&proposer.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(proposal_id: u64)]
+pub struct ApproveProposal<'info> {
+    pub approver: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&approver.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+}
+
+#[derive(Accounts)]
+#[instruction(proposal_id: u64)]
+pub struct CancelProposal<'info> {
+    pub canceller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        close = proposer
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// CHECK: Receives the proposal's rent
+    #[account(mut, address = proposal.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&canceller.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteGovernanceUpdate<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        constraint = attestation_authority.is_signer(&executor.key()) @ AttestationError::NotGovernanceSigner
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
 
-#[derive(Accounts)]
-#[instruction(device_id: [u8; 32])]
-pub struct RefreshAttestation<'info> {
-    #[account(mut)]
-    pub device_owner: Signer<'info>,
-    
-    #[account(
-        mut,
-        seeds = [b"attestation", device_id.as_ref()],
-        bump = attestation_record.bump
-    )]
-    pub attestation_record: Account<'info, AttestationRecord>,
-    
     #[account(
-        seeds = [b"manufacturer", attestation_record.manufacturer_id.as_ref()],
-        bump = manufacturer_account.bump
+        mut,
+        close = executor,
+        seeds = [b"proposal", proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
     )]
-    pub manufacturer_account: Account<'info, ManufacturerAccount>,
+    pub proposal: Account<'info, Proposal>,
 }
 
 // Account structures
 #[account]
 pub struct AttestationAuthority {
-    pub authority: Pubkey,
-    pub trusted_manufacturers: Vec<[u8; 32]>,
+    pub signers: Vec<Pubkey>, // Governance signer set
+    pub threshold: u8,        // Approvals needed to execute a proposal
+    pub signer_set_version: u32,
+    pub manufacturer_timelock: i64, // Delay before a proposed manufacturer can be added
+    pub proposal_count: u64,
+    pub total_manufacturers: u64, // Manufacturers themselves live in per-manufacturer PDAs
+    pub manufacturer_generations: u64, // Manufacturers ever added; never decreases
     pub total_attestations: u64,
+    pub max_validity_period: i64,
+    pub fraud_reporter: Option<Pubkey>, // May flag attestations of devices proven to double-sign
     pub bump: u8,
 }
 
 impl AttestationAuthority {
-    pub const LEN: usize = 32 + 4 + (32 * 10) + 8 + 1; // Allow for up to 10 trusted manufacturers
+    pub const LEN: usize = 4 + (32 * MAX_GOVERNANCE_SIGNERS) + 1 + 4 + 8 + 8 + 8 + 8 + 8 + 8 + 33 + 1;
+
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+}
+
+/// A governance action awaiting approval by the signer set. Executing it closes
+/// the account and refunds its rent to the executor.
+#[account]
+pub struct Proposal {
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub approvals: Vec<Pubkey>,
+    pub signer_set_version: u32, // Signer set the approvals were collected under
+    pub created_at: i64,
+    pub executable_at: i64, // Earliest execution time, after any timelock
+    pub executed: bool,
+    pub bump: u8,
+}
+
+impl Proposal {
+    pub const LEN: usize = 8 + 32 + GovernanceAction::LEN + 4 + (32 * MAX_GOVERNANCE_SIGNERS) + 4 + 8 + 8 + 1 + 1;
+
+    /// Check that this proposal authorizes `action` and mark it executed
+    fn consume(&mut self, authority: &AttestationAuthority, action: &GovernanceAction) -> Result<()> {
+        require!(&self.action == action, AttestationError::ProposalActionMismatch);
+        require!(!self.executed, AttestationError::ProposalAlreadyExecuted);
+        require!(
+            self.signer_set_version == authority.signer_set_version,
+            AttestationError::ProposalStale
+        );
+        require!(
+            self.approvals.len() >= authority.threshold as usize,
+            AttestationError::InsufficientApprovals
+        );
+        require!(
+            Clock::get()?.unix_timestamp >= self.executable_at,
+            AttestationError::ProposalTimelocked
+        );
+
+        self.executed = true;
+        Ok(())
+    }
 }
 
 #[account]
@@ -319,32 +2119,337 @@ pub struct ManufacturerAccount {
     pub manufacturer_id: [u8; 32],
     pub name: String,
     pub public_key: [u8; 32],
+    pub hardware_type: HardwareType,
+    pub policy: AttestationPolicy,
+    pub previous_public_key: Option<[u8; 32]>,
+    pub previous_key_valid_until: i64,
     pub is_active: bool,
+    pub deactivated_at: Option<i64>,
     pub devices_attested: u64,
     pub created_at: i64,
+    pub generation: u64, // Distinguishes this account from earlier ones under the same ID
     pub bump: u8,
 }
 
 impl ManufacturerAccount {
-    pub const LEN: usize = 32 + 4 + 50 + 32 + 1 + 8 + 8 + 1; // 50 chars for name
+    pub const LEN: usize = 32 + 4 + 50 + 32 + 1 + AttestationPolicy::LEN + 33 + 8 + 1 + 9 + 8 + 8 + 8 + 1; // 50 chars for name
+
+    /// Validity period for new attestations, bounded by the protocol maximum
+    pub fn validity_period(&self, max_validity_period: i64) -> i64 {
+        self.policy.validity_period.min(max_validity_period)
+    }
+
+    /// Whether attestations made under `key` are still trusted at `current_time`
+    pub fn is_key_trusted(&self, key: &[u8; 32], current_time: i64) -> bool {
+        key == &self.public_key
+            || (self.previous_public_key.as_ref() == Some(key)
+                && current_time < self.previous_key_valid_until)
+    }
 }
 
 #[account]
 pub struct AttestationRecord {
     pub device_id: [u8; 32],
+    pub device_owner: Pubkey,
+    pub device_key: [u8; 32], // Device attestation key that signs quotes; see KeyAlgorithm
+    pub device_key_algorithm: KeyAlgorithm,
     pub manufacturer_id: [u8; 32],
+    pub manufacturer_key: [u8; 32], // Manufacturer key the attestation was made under
+    pub manufacturer_generation: u64, // Generation of the manufacturer account it was made under
     pub attestation_quote: AttestationQuote,
     pub device_certificate: [u8; 1024],
+    pub quote_format: QuoteFormat,
+    pub security_level: SecurityLevel,
+    pub verified_boot_state: Option<VerifiedBootState>,
+    pub measurement_set_id: [u8; 32],
     pub status: AttestationStatus,
     pub created_at: i64,
+    pub attested_at: i64, // When the current quote was installed
     pub expires_at: i64,
     pub revoked_at: Option<i64>,
     pub revocation_reason: Option<RevocationReason>,
+    pub status_before_revocation: Option<AttestationStatus>, // Restored if the revocation is undone
+    pub history_length: u32, // Number of replaced quotes kept in history entries
+    pub series: u32,         // Which of the device's records this is; see DeviceSeries
+    pub attested_by: Pubkey, // Attester that created the record
+    pub endorsements: u8,    // Distinct attesters that vouched for the current quote
+    pub cosigned: bool,      // Manufacturer has confirmed the current quote
+    pub certificate_serial_hash: Option<[u8; 32]>, // SHA-256 of the X.509 serial, if the certificate parses
     pub bump: u8,
 }
 
 impl AttestationRecord {
-    pub const LEN: usize = 32 + 32 + AttestationQuote::LEN + 1024 + 1 + 8 + 8 + 9 + 2 + 1;
+    pub const LEN: usize = 32 + 32 + 32 + 32 + 32 + 8 + AttestationQuote::LEN + 1024 + 1 + 1 + 2 + 32 + 1 + 8 + 8 + 8 + 9 + 2 + 2 + 4 + 4 + 32 + 1 + 1 + 33 + 1 + 1;
+}
+
+impl AttestationRecord {
+    /// Fill in a newly created record from a verified quote
+    #[allow(clippy::too_many_arguments)]
+    fn initialize(
+        &mut self,
+        device_id: [u8; 32],
+        device_owner: Pubkey,
+        manufacturer: &ManufacturerAccount,
+        max_validity_period: i64,
+        attestation_quote: AttestationQuote,
+        device_certificate: [u8; 1024],
+        quote_format: QuoteFormat,
+    ) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+
+        self.device_id = device_id;
+        self.device_owner = device_owner;
+        self.device_key = attestation_quote.public_key;
+        self.device_key_algorithm = KeyAlgorithm::Ed25519;
+        self.manufacturer_id = manufacturer.manufacturer_id;
+        self.manufacturer_key = manufacturer.public_key;
+        self.manufacturer_generation = manufacturer.generation;
+        self.measurement_set_id = calculate_measurement_set_id(&attestation_quote.measurements);
+        self.attestation_quote = attestation_quote;
+        self.certificate_serial_hash = certificate_serial_hash(&device_certificate);
+        self.device_certificate = device_certificate;
+        self.quote_format = quote_format;
+        self.security_level = manufacturer.hardware_type.security_level();
+        self.verified_boot_state = None;
+        // Manufacturers may require a co-signature or further endorsements first
+        self.endorsements = 1;
+        self.cosigned = false;
+        self.status = if self.is_fully_endorsed(&manufacturer.policy) {
+            AttestationStatus::Valid
+        } else {
+            AttestationStatus::Pending
+        };
+        self.created_at = current_time;
+        self.attested_at = current_time;
+        self.expires_at = current_time + manufacturer.validity_period(max_validity_period);
+        self.history_length = 0;
+        Ok(())
+    }
+
+    /// Whether the record was made under `manufacturer`'s current account, rather than
+    /// one that has since been removed and re-added under the same ID
+    fn is_from(&self, manufacturer: &ManufacturerAccount) -> bool {
+        self.manufacturer_generation == manufacturer.generation
+    }
+
+    /// Check that `refresher` may give this record a new quote: the record is Valid, or
+    /// Expired (a fresh quote revives it), it is still trusted under `manufacturer`, and
+    /// the refresher is the device owner, the manufacturer or a registered attester
+    fn authorize_refresh(
+        &self,
+        device_id: &[u8; 32],
+        manufacturer: &ManufacturerAccount,
+        refresher: &Pubkey,
+        registered_attester: bool,
+    ) -> Result<()> {
+        require!(self.device_id == *device_id, AttestationError::DeviceIdMismatch);
+        require!(
+            self.status == AttestationStatus::Valid || self.status == AttestationStatus::Expired,
+            AttestationError::InvalidAttestation
+        );
+
+        require!(manufacturer.is_active, AttestationError::UntrustedManufacturer);
+        require!(self.is_from(manufacturer), AttestationError::StaleManufacturer);
+
+        require!(
+            *refresher == self.device_owner
+                || refresher.to_bytes() == manufacturer.public_key
+                || registered_attester,
+            AttestationError::UnauthorizedRefresh
+        );
+        Ok(())
+    }
+
+    /// Replace the quote with a verified new one, keeping the old one in the append-only
+    /// history, and start a new validity period under the manufacturer's current key
+    fn replace_quote(
+        &mut self,
+        history_entry: &mut AttestationHistoryEntry,
+        history_bump: u8,
+        new_attestation_quote: AttestationQuote,
+        manufacturer: &ManufacturerAccount,
+        max_validity_period: i64,
+    ) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+
+        history_entry.device_id = self.device_id;
+        history_entry.sequence = self.history_length;
+        history_entry.quote_hash = calculate_quote_hash(&self.device_id, &self.attestation_quote);
+        history_entry.measurement_set_id = self.measurement_set_id;
+        history_entry.measurements = self.attestation_quote.measurements.clone();
+        history_entry.manufacturer_key = self.manufacturer_key;
+        history_entry.quote_timestamp = self.attestation_quote.timestamp;
+        history_entry.valid_from = self.attested_at;
+        history_entry.replaced_at = current_time;
+        history_entry.bump = history_bump;
+
+        self.measurement_set_id = calculate_measurement_set_id(&new_attestation_quote.measurements);
+        self.attestation_quote = new_attestation_quote;
+        self.manufacturer_key = manufacturer.public_key;
+        self.status = AttestationStatus::Valid;
+        self.attested_at = current_time;
+        self.expires_at = current_time + manufacturer.validity_period(max_validity_period);
+        self.history_length += 1;
+        Ok(())
+    }
+
+    /// Revoke the record, remembering its status in case the revocation is undone
+    fn revoke(&mut self, reason: RevocationReason, current_time: i64) {
+        if self.status != AttestationStatus::Revoked {
+            self.status_before_revocation = Some(self.status.clone());
+        }
+        self.status = AttestationStatus::Revoked;
+        self.revocation_reason = Some(reason);
+        self.revoked_at = Some(current_time);
+    }
+
+    /// Whether an earlier instruction in this transaction checked `signature` by the
+    /// device key over `message`, through the precompile for the key's algorithm
+    pub fn verify_device_signature(
+        &self,
+        instructions: &AccountInfo,
+        message: &[u8],
+        signature: &[u8; 64],
+    ) -> Result<bool> {
+        match self.device_key_algorithm {
+            KeyAlgorithm::Ed25519 => verify_ed25519_instruction(instructions, &self.device_key, message, signature),
+            KeyAlgorithm::EcdsaP256 => verify_secp256r1_instruction(
+                instructions,
+                |public_key| Sha256::digest(public_key)[..] == self.device_key,
+                message,
+                signature,
+            ),
+        }
+    }
+
+    /// CRL bucket holding this record's certificate serial, if it were revoked
+    pub fn crl_bucket(&self) -> u8 {
+        self.certificate_serial_hash.map_or(0, |serial_hash| serial_hash[0])
+    }
+
+    /// Whether the manufacturer's co-signature and endorsement requirements are met
+    fn is_fully_endorsed(&self, policy: &AttestationPolicy) -> bool {
+        self.endorsements >= policy.endorsement_threshold && (self.cosigned || !policy.require_cosign)
+    }
+
+    /// Move a pending record to Valid if it is fully endorsed, starting its validity now
+    fn activate_if_endorsed(
+        &mut self,
+        manufacturer: &ManufacturerAccount,
+        max_validity_period: i64,
+        current_time: i64,
+    ) -> bool {
+        if !self.is_fully_endorsed(&manufacturer.policy) {
+            return false;
+        }
+        self.status = AttestationStatus::Valid;
+        self.expires_at = current_time + manufacturer.validity_period(max_validity_period);
+        true
+    }
+}
+
+/// Number of attestation records ever created for a device. Outlives the records
+/// themselves, so history entries and endorsements keyed by a record's series never
+/// collide with those of an earlier record for the same device.
+#[account]
+pub struct DeviceSeries {
+    pub device_id: [u8; 32],
+    pub records_created: u32,
+    pub bump: u8,
+}
+
+impl DeviceSeries {
+    pub const LEN: usize = 32 + 4 + 1;
+
+    /// Claim the series number for a new record of `device_id`
+    fn next(&mut self, device_id: [u8; 32], bump: u8) -> u32 {
+        self.device_id = device_id;
+        self.bump = bump;
+        self.records_created += 1;
+        self.records_created - 1
+    }
+}
+
+/// A quote that was replaced by a refresh. Entries are never modified or closed,
+/// so they show what a device was running at any point in time.
+#[account]
+pub struct AttestationHistoryEntry {
+    pub device_id: [u8; 32],
+    pub sequence: u32,
+    pub quote_hash: [u8; 32],
+    pub measurement_set_id: [u8; 32],
+    pub measurements: Vec<[u8; 32]>,
+    pub manufacturer_key: [u8; 32],
+    pub quote_timestamp: i64,
+    pub valid_from: i64,
+    pub replaced_at: i64,
+    pub bump: u8,
+}
+
+impl AttestationHistoryEntry {
+    pub const LEN: usize = 32 + 4 + 32 + 32 + 4 + (32 * 8) + 32 + 8 + 8 + 8 + 1; // Up to 8 measurements
+}
+
+/// Attesters other than the creator that endorsed a pending attestation's quote.
+/// Keyed by the record's series, so a re-created record starts with no endorsers.
+#[account]
+pub struct PendingEndorsement {
+    pub device_id: [u8; 32],
+    pub quote_hash: [u8; 32],
+    pub endorsers: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl PendingEndorsement {
+    pub const MAX_ENDORSERS: usize = MAX_ENDORSEMENT_THRESHOLD as usize - 1; // Creator is not listed
+    pub const LEN: usize = 32 + 32 + 4 + (32 * Self::MAX_ENDORSERS) + 1;
+}
+
+/// Revoked certificate serials imported from a manufacturer CRL, one bucket per
+/// first byte of the serial hash. Entries are kept sorted.
+#[account]
+pub struct CrlBucket {
+    pub manufacturer_id: [u8; 32],
+    pub bucket: u8,
+    pub crl_number: u64, // Number of the last CRL imported into this bucket
+    pub serial_hashes: Vec<[u8; 32]>,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl CrlBucket {
+    /// Account size with room for `entries` serial hashes
+    pub fn space(entries: usize) -> usize {
+        8 + 32 + 1 + 8 + 4 + (32 * entries) + 8 + 1
+    }
+
+    pub fn contains(&self, serial_hash: &[u8; 32]) -> bool {
+        self.serial_hashes.binary_search(serial_hash).is_ok()
+    }
+}
+
+#[account]
+pub struct AttesterAccount {
+    pub manufacturer_id: [u8; 32],
+    pub attester: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl AttesterAccount {
+    pub const LEN: usize = 32 + 32 + 8 + 1;
+}
+
+#[account]
+pub struct DeprecatedMeasurementSet {
+    pub measurement_set_id: [u8; 32],
+    pub deprecated_by: Pubkey,
+    pub deprecated_at: i64,
+    pub bump: u8,
+}
+
+impl DeprecatedMeasurementSet {
+    pub const LEN: usize = 32 + 32 + 8 + 1;
 }
 
 // Data structures
@@ -362,21 +2467,238 @@ impl AttestationQuote {
     pub const LEN: usize = 4 + 64 + 32 + 32 + 8 + 4 + (32 * 8); // Allow for up to 8 measurements
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AttestationPolicy {
+    pub validity_period: i64, // Seconds an attestation stays valid
+    pub grace_period: i64,    // Seconds after expiry it still verifies as needing refresh
+    pub require_cosign: bool, // New attestations stay Pending until the manufacturer confirms
+    pub endorsement_threshold: u8, // Registered attesters, including the creator, that must endorse
+}
+
+impl AttestationPolicy {
+    pub const LEN: usize = 8 + 8 + 1 + 1;
+}
+
+/// Administrative actions that need approval from the governance signer set
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum GovernanceAction {
+    AddManufacturer {
+        manufacturer_id: [u8; 32],
+        name: String,
+        public_key: [u8; 32],
+        hardware_type: HardwareType,
+    },
+    DeactivateManufacturer { manufacturer_id: [u8; 32] },
+    RotateManufacturerKey {
+        manufacturer_id: [u8; 32],
+        new_public_key: [u8; 32],
+        overlap_period: i64,
+    },
+    RemoveManufacturer { manufacturer_id: [u8; 32] },
+    RevokeAttestation { device_id: [u8; 32], reason: RevocationReason },
+    ReinstateAttestation { device_id: [u8; 32] },
+    CloseAttestation { device_id: [u8; 32] },
+    DeprecateMeasurementSet { measurement_set_id: [u8; 32] },
+    SetMaxValidityPeriod { max_validity_period: i64 },
+    SetSigners { signers: Vec<Pubkey>, threshold: u8 },
+    SetManufacturerTimelock { manufacturer_timelock: i64 },
+    SetFraudReporter { fraud_reporter: Option<Pubkey> },
+}
+
+impl GovernanceAction {
+    pub const LEN: usize = 1 + 4 + (32 * MAX_GOVERNANCE_SIGNERS) + 1; // Largest variant is SetSigners
+
+    /// Reject actions that could never be executed or would not fit a proposal, so
+    /// that out-of-range parameters fail at propose time rather than after approval
+    fn validate(&self) -> Result<()> {
+        match self {
+            GovernanceAction::AddManufacturer { name, public_key, .. } => {
+                require!(
+                    name.len() <= MAX_MANUFACTURER_NAME_LEN,
+                    AttestationError::InvalidGovernanceAction
+                );
+                require!(*public_key != [0u8; 32], AttestationError::InvalidManufacturerKey);
+            }
+            GovernanceAction::RotateManufacturerKey { new_public_key, overlap_period, .. } => {
+                require!(*new_public_key != [0u8; 32], AttestationError::InvalidManufacturerKey);
+                require!(
+                    (0..=MAX_KEY_ROTATION_OVERLAP).contains(overlap_period),
+                    AttestationError::InvalidOverlapPeriod
+                );
+            }
+            GovernanceAction::SetMaxValidityPeriod { max_validity_period } => {
+                require!(
+                    (1..=MAX_VALIDITY_PERIOD_LIMIT).contains(max_validity_period),
+                    AttestationError::InvalidValidityPolicy
+                );
+            }
+            GovernanceAction::SetSigners { signers, threshold } => {
+                validate_signer_set(signers, *threshold)?;
+            }
+            GovernanceAction::SetManufacturerTimelock { manufacturer_timelock } => {
+                require!(*manufacturer_timelock >= 0, AttestationError::InvalidGovernanceAction);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum HardwareType {
+    ShiftDevice,
+    LedgerV2,
+    TrustedExecutionEnvironment,
+}
+
+impl HardwareType {
+    /// Default validity for a hardware class; TEEs are expected to re-attest daily
+    pub fn default_validity_period(&self) -> i64 {
+        match self {
+            HardwareType::TrustedExecutionEnvironment => 86400,
+            HardwareType::ShiftDevice | HardwareType::LedgerV2 => 86400 * 30,
+        }
+    }
+
+    /// Security level implied by the hardware class when the evidence does not state one
+    pub fn security_level(&self) -> SecurityLevel {
+        match self {
+            HardwareType::TrustedExecutionEnvironment => SecurityLevel::TrustedEnvironment,
+            HardwareType::ShiftDevice | HardwareType::LedgerV2 => SecurityLevel::SecureElement,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum SecurityLevel {
+    Software,
+    TrustedEnvironment,
+    StrongBox,
+    SecureElement,
+}
+
+impl SecurityLevel {
+    /// Relative strength of key protection; StrongBox is a secure element
+    pub fn rank(&self) -> u8 {
+        match self {
+            SecurityLevel::Software => 0,
+            SecurityLevel::TrustedEnvironment => 1,
+            SecurityLevel::StrongBox | SecurityLevel::SecureElement => 2,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum VerifiedBootState {
+    Verified,
+    SelfSigned,
+    Unverified,
+    Failed,
+}
+
+/// Signature algorithm of a device key. P-256 keys do not fit the record's 32-byte
+/// `device_key`, so it holds the SHA-256 of the SEC1 compressed key instead.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum KeyAlgorithm {
+    Ed25519,
+    EcdsaP256,
+}
+
+/// TPM attestation key that signed a quote
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum TpmAttestationKey {
+    Ed25519([u8; 32]),
+    EcdsaP256([u8; 33]), // SEC1 compressed point
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum QuoteFormat {
+    Shift, // AttestationQuote signed over calculate_quote_hash
+    Tpm2,  // TPM 2.0 TPMS_ATTEST quote
+    AndroidKeyAttestation, // Keystore certificate with the key description extension
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum AttestationValidity {
+    Valid,
+    NeedsRefresh, // Expired but within the manufacturer's grace period
+}
+
+/// What a verified attestation vouches for, returned by `verify_attestation`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct AttestationSummary {
+    pub device_id: [u8; 32],
+    pub device_key: [u8; 32],
+    pub device_key_algorithm: KeyAlgorithm,
+    pub manufacturer_id: [u8; 32],
+    pub hardware_type: HardwareType,
+    pub security_level: SecurityLevel,
+    pub verified_boot_state: Option<VerifiedBootState>,
+    pub measurement_set_id: [u8; 32],
+    pub expires_at: i64,
+    pub validity: AttestationValidity,
+}
+
+impl AttestationSummary {
+    /// Whether the device's keys are protected at least as strongly as `required`
+    pub fn meets_security_level(&self, required: &SecurityLevel) -> bool {
+        self.security_level.rank() >= required.rank()
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum AttestationStatus {
     Valid,
     Expired,
     Revoked,
     Pending,
+    Flagged, // Device proven to misuse its keys; awaiting a governance decision
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum RevocationReason {
     Compromised,
     Expired,
     ManufacturerRevoked,
     UserRequested,
     Other,
+    Lost, // Reported missing; may be reinstated if recovered
+}
+
+impl RevocationReason {
+    /// Whether a revocation for this reason may be undone by governance
+    pub fn is_reversible(&self) -> bool {
+        matches!(self, RevocationReason::UserRequested | RevocationReason::Lost)
+    }
+}
+
+// Events
+#[event]
+pub struct AttestationExpired {
+    pub device_id: [u8; 32],
+    pub expires_at: i64,
+    pub expired_at: i64,
+}
+
+#[event]
+pub struct StaleAttestationClosed {
+    pub device_id: [u8; 32],
+    pub attested_by: Pubkey,
+    pub closed_at: i64,
+}
+
+#[event]
+pub struct AttestationFlagged {
+    pub device_id: [u8; 32],
+    pub reporter: Pubkey,
+    pub flagged_at: i64,
+}
+
+#[event]
+pub struct AttestationConfirmed {
+    pub device_id: [u8; 32],
+    pub manufacturer_id: [u8; 32],
+    pub confirmed_at: i64,
 }
 
 // Helper functions
@@ -393,6 +2715,390 @@ fn verify_attestation_quote(
         && !quote.measurements.is_empty()
 }
 
+/// SHA-256 of a DER certificate's serial number, as listed in CRL buckets.
+/// Trailing zero padding after the certificate is ignored.
+pub fn certificate_serial_hash(certificate: &[u8]) -> Option<[u8; 32]> {
+    let (_, certificate) = X509Certificate::from_der(certificate).ok()?;
+    Some(Sha256::digest(certificate.raw_serial()).into())
+}
+
+/// A signer set must be non-empty, free of duplicates and satisfiable by its threshold
+fn validate_signer_set(signers: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(
+        !signers.is_empty() && signers.len() <= MAX_GOVERNANCE_SIGNERS,
+        AttestationError::InvalidSignerSet
+    );
+    require!(
+        threshold > 0 && threshold as usize <= signers.len(),
+        AttestationError::InvalidSignerSet
+    );
+    require!(
+        signers
+            .iter()
+            .enumerate()
+            .all(|(i, signer)| !signers[..i].contains(signer)),
+        AttestationError::InvalidSignerSet
+    );
+    Ok(())
+}
+
+/// Identifier of a firmware measurement set, shared by every device running the same firmware
+pub fn calculate_measurement_set_id(measurements: &[[u8; 32]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"SHIFT_MEASUREMENT_SET");
+    for measurement in measurements {
+        hasher.update(measurement);
+    }
+
+    let result = hasher.finalize();
+    let mut measurement_set_id = [0u8; 32];
+    measurement_set_id.copy_from_slice(&result);
+    measurement_set_id
+}
+
+/// Refuse a quote whose measurement set has been deprecated. `measurement_deprecation`
+/// must be the set's deprecation marker PDA, which exists only once governance deprecates it.
+fn require_measurements_not_deprecated(
+    measurement_deprecation: &AccountInfo,
+    measurements: &[[u8; 32]],
+) -> Result<()> {
+    let measurement_set_id = calculate_measurement_set_id(measurements);
+    let (marker, _) = Pubkey::find_program_address(
+        &[b"deprecated_measurements", measurement_set_id.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(
+        measurement_deprecation.key(),
+        marker,
+        AttestationError::MeasurementDeprecationMismatch
+    );
+    require!(
+        measurement_deprecation.data_is_empty(),
+        AttestationError::MeasurementSetDeprecated
+    );
+    Ok(())
+}
+
+/// Measurement standing for a TPM quote's PCR selection, so quotes over different PCRs
+/// fall in different measurement sets even when their PCR digests agree
+fn pcr_selection_measurement(pcr_selections: &[tpm::PcrSelection]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"SHIFT_TPM_PCR_SELECTION");
+    for selection in pcr_selections {
+        hasher.update(selection.hash_alg.to_be_bytes());
+        hasher.update([selection.pcr_select.len() as u8]);
+        hasher.update(&selection.pcr_select);
+    }
+    hasher.finalize().into()
+}
+
+/// Challenge hardware evidence for `device_id` must carry: the device ID bound to the hash
+/// of `slot`, so the evidence cannot be produced before that slot or reused for another
+/// device, and goes stale once the slot leaves the SlotHashes sysvar
+fn device_challenge(slot_hashes: &AccountInfo, device_id: &[u8; 32], slot: u64) -> Result<[u8; 32]> {
+    let slot_hash = recent_slot_hash(slot_hashes, slot).ok_or(AttestationError::UnknownChallengeSlot)?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"SHIFT_DEVICE_CHALLENGE");
+    hasher.update(device_id);
+    hasher.update(slot.to_le_bytes());
+    hasher.update(slot_hash);
+    Ok(hasher.finalize().into())
+}
+
+/// Hash of a slot still held by the SlotHashes sysvar (the most recent 512). Read from the
+/// raw account data, a u64 entry count followed by (u64 slot, hash) entries, since the
+/// sysvar is too large to deserialize on-chain.
+fn recent_slot_hash(slot_hashes: &AccountInfo, slot: u64) -> Option<[u8; 32]> {
+    const ENTRY_LEN: usize = 8 + 32;
+
+    let data = slot_hashes.try_borrow_data().ok()?;
+    let count = u64::from_le_bytes(data.get(..8)?.try_into().ok()?) as usize;
+    data.get(8..)?
+        .chunks_exact(ENTRY_LEN)
+        .take(count)
+        .find(|entry| entry[..8] == slot.to_le_bytes())
+        .map(|entry| entry[8..].try_into().unwrap())
+}
+
+/// Check a TPM quote signed by `attestation_key` over the device's challenge at
+/// `challenge_slot`. Returns the quote as the record keeps it, with the PCR selection
+/// and the SHA-256 PCR digest as its measurements, and the device key's algorithm.
+fn verify_tpm_quote(
+    instructions: &AccountInfo,
+    slot_hashes: &AccountInfo,
+    device_id: &[u8; 32],
+    challenge_slot: u64,
+    attestation_key: TpmAttestationKey,
+    tpms_attest: &[u8],
+    tpmt_signature: &[u8],
+) -> Result<(AttestationQuote, KeyAlgorithm)> {
+    let quote = tpm::parse_tpms_attest(tpms_attest).ok_or(AttestationError::InvalidTpmQuote)?;
+    let challenge = device_challenge(slot_hashes, device_id, challenge_slot)?;
+    require!(
+        quote.extra_data.as_slice() == challenge.as_slice(),
+        AttestationError::ChallengeMismatch
+    );
+    let pcr_digest = quote.sha256_pcr_digest().ok_or(AttestationError::InvalidTpmQuote)?;
+
+    let tpm_signature = tpm::parse_signature(tpmt_signature)
+        .ok_or(AttestationError::UnsupportedSignatureScheme)?;
+    require!(
+        tpm_signature.hash_alg == tpm::TPM_ALG_SHA256,
+        AttestationError::UnsupportedSignatureScheme
+    );
+    let signature = tpm_signature.signature;
+
+    let signed_by = (attestation_key, tpm_signature.sig_alg);
+    let (device_key, device_key_algorithm, signature_verified) = match signed_by {
+        (TpmAttestationKey::Ed25519(key), tpm::TPM_ALG_EDDSA) => {
+            let attest_digest: [u8; 32] = Sha256::digest(tpms_attest).into();
+            let verified = verify_ed25519_instruction(instructions, &key, &attest_digest, &signature)?;
+            (key, KeyAlgorithm::Ed25519, verified)
+        }
+        (TpmAttestationKey::EcdsaP256(key), tpm::TPM_ALG_ECDSA) => {
+            let verified =
+                verify_secp256r1_instruction(instructions, |signer| signer == key, tpms_attest, &signature)?;
+            (Sha256::digest(key).into(), KeyAlgorithm::EcdsaP256, verified)
+        }
+        _ => return err!(AttestationError::UnsupportedSignatureScheme),
+    };
+    require!(signature_verified, AttestationError::InvalidQuoteSignature);
+
+    let attestation_quote = AttestationQuote {
+        version: TPM2_QUOTE_VERSION,
+        signature,
+        public_key: device_key,
+        nonce: challenge,
+        timestamp: Clock::get()?.unix_timestamp, // TPM clock is not wall-clock time
+        measurements: vec![pcr_selection_measurement(&quote.pcr_selections), pcr_digest],
+    };
+    Ok((attestation_quote, device_key_algorithm))
+}
+
+/// What an Android attestation certificate establishes about its device
+struct AndroidEvidence {
+    attestation_quote: AttestationQuote,
+    device_key_algorithm: KeyAlgorithm,
+    device_certificate: [u8; 1024],
+    security_level: SecurityLevel,
+    verified_boot_state: VerifiedBootState,
+}
+
+/// Check an Android Key Attestation leaf certificate over `challenge`, from a hardware
+/// keystore, for a key whose signatures can be verified on-chain. The boot measurement
+/// is its one measurement.
+fn verify_android_attestation(attestation_certificate: &[u8], challenge: &[u8; 32]) -> Result<AndroidEvidence> {
+    require!(
+        attestation_certificate.len() <= 1024,
+        AttestationError::InvalidDeviceCertificate
+    );
+
+    let attestation = android::parse_attestation_certificate(attestation_certificate)
+        .ok_or(AttestationError::InvalidAndroidAttestation)?;
+    let key_description = attestation.key_description;
+
+    require!(
+        key_description.attestation_challenge.as_slice() == challenge.as_slice(),
+        AttestationError::ChallengeMismatch
+    );
+    require!(
+        key_description.attestation_security_level != SecurityLevel::Software,
+        AttestationError::InsufficientSecurityLevel
+    );
+
+    // The device key signs refreshes and payments, so it must be verifiable on-chain
+    let (device_key, device_key_algorithm) = match attestation.attested_key {
+        Some(android::AttestedKey::Ed25519(key)) => (key, KeyAlgorithm::Ed25519),
+        Some(android::AttestedKey::EcdsaP256(key)) => (Sha256::digest(key).into(), KeyAlgorithm::EcdsaP256),
+        None => return err!(AttestationError::UnsupportedSignatureScheme),
+    };
+    let root_of_trust = key_description
+        .root_of_trust
+        .ok_or(AttestationError::InvalidAndroidAttestation)?;
+
+    // The verified boot hash identifies the OS image; older versions only have the boot key
+    let boot_measurement: [u8; 32] = match root_of_trust.verified_boot_hash.as_deref() {
+        Some(hash) if hash.len() == 32 => hash.try_into().unwrap(),
+        _ => Sha256::digest(&root_of_trust.verified_boot_key).into(),
+    };
+
+    let mut device_certificate = [0u8; 1024];
+    device_certificate[..attestation_certificate.len()].copy_from_slice(attestation_certificate);
+
+    Ok(AndroidEvidence {
+        attestation_quote: AttestationQuote {
+            version: key_description.attestation_version,
+            signature: [0u8; 64], // Proof lives in the certificate, not a separate quote signature
+            public_key: device_key,
+            nonce: *challenge,
+            timestamp: Clock::get()?.unix_timestamp,
+            measurements: vec![boot_measurement],
+        },
+        device_key_algorithm,
+        device_certificate,
+        security_level: key_description.attestation_security_level,
+        verified_boot_state: root_of_trust.verified_boot_state,
+    })
+}
+
+/// Whether `certificate` certifies `attestation_key` under the manufacturer's key: the
+/// key is its subject key, and an earlier Ed25519 program instruction checked the
+/// manufacturer's signature over its TBSCertificate
+fn certificate_certifies_key(
+    instructions: &AccountInfo,
+    certificate: &[u8],
+    manufacturer_key: &[u8; 32],
+    attestation_key: &TpmAttestationKey,
+) -> Result<bool> {
+    let Ok((_, certificate)) = X509Certificate::from_der(certificate) else {
+        return Ok(false);
+    };
+
+    let certifies_key = match (attestation_key, android::subject_key(&certificate)) {
+        (TpmAttestationKey::Ed25519(key), Some(android::AttestedKey::Ed25519(subject))) => *key == subject,
+        (TpmAttestationKey::EcdsaP256(key), Some(android::AttestedKey::EcdsaP256(subject))) => *key == subject,
+        _ => false,
+    };
+    let signature: Option<[u8; 64]> = certificate.signature_value.data.as_ref().try_into().ok();
+    match signature {
+        Some(signature)
+            if certifies_key && certificate.signature_algorithm.algorithm.as_bytes() == android::ED25519_OID =>
+        {
+            verify_ed25519_instruction(instructions, manufacturer_key, certificate.tbs_certificate.as_ref(), &signature)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Hash of the quote contents that the device key signs
+pub fn calculate_quote_hash(device_id: &[u8; 32], quote: &AttestationQuote) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"SHIFT_ATTESTATION_QUOTE");
+    hasher.update(device_id);
+    hasher.update(quote.version.to_le_bytes());
+    hasher.update(quote.public_key);
+    hasher.update(quote.nonce);
+    hasher.update(quote.timestamp.to_le_bytes());
+    for measurement in quote.measurements.iter() {
+        hasher.update(measurement);
+    }
+
+    let result = hasher.finalize();
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&result);
+    hash
+}
+
+/// Look for an Ed25519 program instruction earlier in this transaction that checked
+/// `signature` by `public_key` over `message`. The precompile has already rejected the
+/// transaction if the signature was bad, so a matching instruction proves the signature.
+pub fn verify_ed25519_instruction(
+    instructions: &AccountInfo,
+    public_key: &[u8; 32],
+    message: &[u8],
+    signature: &[u8; 64],
+) -> Result<bool> {
+    let current_index = instructions_sysvar::load_current_index_checked(instructions)?;
+
+    for index in 0..current_index {
+        let instruction = instructions_sysvar::load_instruction_at_checked(index as usize, instructions)?;
+        if instruction.program_id == ed25519_program::ID
+            && precompile_instruction_matches(
+                &instruction.data,
+                ED25519_PUBLIC_KEY_LEN,
+                |key| key == public_key,
+                message,
+                signature,
+            )
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Look for a secp256r1 program instruction earlier in this transaction that checked
+/// `signature` over `message` by a SEC1 compressed key accepted by `key_matches`
+pub fn verify_secp256r1_instruction(
+    instructions: &AccountInfo,
+    key_matches: impl Fn(&[u8]) -> bool,
+    message: &[u8],
+    signature: &[u8; 64],
+) -> Result<bool> {
+    let current_index = instructions_sysvar::load_current_index_checked(instructions)?;
+
+    for index in 0..current_index {
+        let instruction = instructions_sysvar::load_instruction_at_checked(index as usize, instructions)?;
+        if instruction.program_id == SECP256R1_PROGRAM_ID
+            && precompile_instruction_matches(
+                &instruction.data,
+                P256_COMPRESSED_KEY_LEN,
+                &key_matches,
+                message,
+                signature,
+            )
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+const ED25519_PUBLIC_KEY_LEN: usize = 32;
+const P256_COMPRESSED_KEY_LEN: usize = 33;
+
+/// Whether a signature verification precompile instruction (Ed25519 and secp256r1 share
+/// the layout) checked `signature` over `message` by a key accepted by `key_matches`
+fn precompile_instruction_matches(
+    data: &[u8],
+    key_len: usize,
+    key_matches: impl Fn(&[u8]) -> bool,
+    message: &[u8],
+    signature: &[u8; 64],
+) -> bool {
+    const OFFSETS_START: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    const THIS_INSTRUCTION: u16 = u16::MAX;
+
+    let read_u16 = |at: usize| -> Option<u16> {
+        data.get(at..at + 2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+    };
+    let slice = |offset: u16, len: usize| -> Option<&[u8]> {
+        data.get(offset as usize..offset as usize + len)
+    };
+
+    let signature_count = match data.first() {
+        Some(count) => *count as usize,
+        None => return false,
+    };
+
+    (0..signature_count).any(|i| {
+        let field = |n: usize| read_u16(OFFSETS_START + i * OFFSETS_LEN + n * 2);
+
+        // Only accept data carried inside the Ed25519 instruction itself
+        match (field(0), field(1), field(2), field(3), field(4), field(5), field(6)) {
+            (
+                Some(signature_offset),
+                Some(THIS_INSTRUCTION),
+                Some(key_offset),
+                Some(THIS_INSTRUCTION),
+                Some(message_offset),
+                Some(message_size),
+                Some(THIS_INSTRUCTION),
+            ) => {
+                message_size as usize == message.len()
+                    && slice(signature_offset, 64) == Some(&signature[..])
+                    && slice(key_offset, key_len).is_some_and(&key_matches)
+                    && slice(message_offset, message.len()) == Some(message)
+            }
+            _ => false,
+        }
+    })
+}
+
 fn verify_device_certificate(certificate: &[u8; 1024], manufacturer_key: &[u8; 32]) -> bool {
     // In production, this would verify the X.509 certificate chain
     // For demo purposes, we'll do basic validation
@@ -416,4 +3122,86 @@ pub enum AttestationError {
     InvalidAttestation,
     #[msg("Attestation expired")]
     AttestationExpired,
+    #[msg("Measurement set deprecated")]
+    MeasurementSetDeprecated,
+    #[msg("Manufacturer key retired")]
+    ManufacturerKeyRetired,
+    #[msg("Invalid manufacturer key")]
+    InvalidManufacturerKey,
+    #[msg("Invalid key rotation overlap period")]
+    InvalidOverlapPeriod,
+    #[msg("Manufacturer must be deactivated first")]
+    ManufacturerStillActive,
+    #[msg("Signer may not refresh this attestation")]
+    UnauthorizedRefresh,
+    #[msg("Quote is not from the attested device key")]
+    DeviceKeyMismatch,
+    #[msg("Invalid quote signature")]
+    InvalidQuoteSignature,
+    #[msg("Signer is not the manufacturer key")]
+    UnauthorizedManufacturer,
+    #[msg("Invalid attestation validity policy")]
+    InvalidValidityPolicy,
+    #[msg("Attestation has not expired")]
+    AttestationNotExpired,
+    #[msg("Attestation is not pending")]
+    AttestationNotPending,
+    #[msg("Attestation is not revoked")]
+    AttestationNotRevoked,
+    #[msg("Revocation reason does not allow reinstatement")]
+    RevocationNotReversible,
+    #[msg("Manufacturer hardware type does not match")]
+    HardwareTypeMismatch,
+    #[msg("Invalid TPM quote")]
+    InvalidTpmQuote,
+    #[msg("Quote does not answer the challenge nonce")]
+    ChallengeMismatch,
+    #[msg("Unsupported signature scheme")]
+    UnsupportedSignatureScheme,
+    #[msg("Invalid Android key attestation")]
+    InvalidAndroidAttestation,
+    #[msg("Insufficient hardware security level")]
+    InsufficientSecurityLevel,
+    #[msg("Endorsement does not match the attestation quote")]
+    EndorsementQuoteMismatch,
+    #[msg("Attester has already endorsed this attestation")]
+    DuplicateEndorsement,
+    #[msg("Device certificate has been revoked by its manufacturer")]
+    CertificateRevoked,
+    #[msg("CRL is not newer than the one already imported")]
+    StaleCrl,
+    #[msg("Serial hash does not belong to this CRL bucket")]
+    CrlBucketMismatch,
+    #[msg("Signer is not part of the governance signer set")]
+    NotGovernanceSigner,
+    #[msg("Invalid governance signer set or threshold")]
+    InvalidSignerSet,
+    #[msg("Invalid governance action")]
+    InvalidGovernanceAction,
+    #[msg("Proposal does not authorize this action")]
+    ProposalActionMismatch,
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Proposal was made under a previous signer set")]
+    ProposalStale,
+    #[msg("Signer has already approved this proposal")]
+    AlreadyApproved,
+    #[msg("Proposal does not have enough approvals")]
+    InsufficientApprovals,
+    #[msg("Proposal timelock has not elapsed")]
+    ProposalTimelocked,
+    #[msg("Signer is not the configured fraud reporter")]
+    UnauthorizedFraudReporter,
+    #[msg("Measurement deprecation account does not match the quote's measurement set")]
+    MeasurementDeprecationMismatch,
+    #[msg("Attestation has already collected the maximum number of endorsements")]
+    TooManyEndorsements,
+    #[msg("Only the proposer can cancel a proposal made under the current signer set")]
+    UnauthorizedCancellation,
+    #[msg("Attestation was made before its manufacturer was removed and added again")]
+    StaleManufacturer,
+    #[msg("Refresh does not match the attestation's quote format")]
+    QuoteFormatMismatch,
+    #[msg("Challenge slot is not among the recent slot hashes")]
+    UnknownChallengeSlot,
 } 
\ No newline at end of file
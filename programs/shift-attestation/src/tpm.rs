@@ -0,0 +1,350 @@
+//! Minimal parsing of TPM 2.0 quote structures (TPMS_ATTEST and TPMT_SIGNATURE).
+//! TPM structures are big-endian and sized buffers (TPM2B) carry a u16 length prefix.
+
+pub const TPM_GENERATED_VALUE: u32 = 0xff54_4347;
+pub const TPM_ST_ATTEST_QUOTE: u16 = 0x8018;
+pub const TPM_ALG_SHA256: u16 = 0x000b;
+pub const TPM_ALG_ECDSA: u16 = 0x0018;
+pub const TPM_ALG_EDDSA: u16 = 0x0060;
+
+/// Order of the P-256 group, big-endian
+const P256_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63, 0x25, 0x51,
+];
+
+/// Upper bound on PCR banks in a selection, to bound parsing work
+pub const MAX_PCR_SELECTIONS: u32 = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PcrSelection {
+    pub hash_alg: u16,
+    pub pcr_select: Vec<u8>, // Bitmap of selected PCR indices
+}
+
+/// The fields of a TPMS_ATTEST quote that attestation cares about
+#[derive(Clone, Debug, PartialEq)]
+pub struct TpmQuote {
+    pub qualified_signer: Vec<u8>,
+    pub extra_data: Vec<u8>, // Caller-provided qualifying data, i.e. the challenge nonce
+    pub clock: u64,
+    pub reset_count: u32,
+    pub restart_count: u32,
+    pub safe: bool,
+    pub firmware_version: u64,
+    pub pcr_selections: Vec<PcrSelection>,
+    pub pcr_digest: Vec<u8>,
+}
+
+impl TpmQuote {
+    /// Whether the quote covers at least one PCR in a SHA-256 bank
+    pub fn selects_sha256_pcrs(&self) -> bool {
+        self.pcr_selections
+            .iter()
+            .any(|selection| selection.hash_alg == TPM_ALG_SHA256 && selection.pcr_select.iter().any(|bits| *bits != 0))
+    }
+
+    /// Digest of the quoted PCRs, if they include a SHA-256 bank
+    pub fn sha256_pcr_digest(&self) -> Option<[u8; 32]> {
+        if !self.selects_sha256_pcrs() {
+            return None;
+        }
+        self.pcr_digest.as_slice().try_into().ok()
+    }
+}
+
+/// A TPMT_SIGNATURE in a scheme that can be checked on-chain
+#[derive(Clone, Debug, PartialEq)]
+pub struct TpmSignature {
+    pub sig_alg: u16,  // TPM_ALG_EDDSA or TPM_ALG_ECDSA
+    pub hash_alg: u16, // Hash the scheme signs with
+    pub signature: [u8; 64], // R || S; ECDSA S is normalized to the lower half of the order
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn tpm2b(&mut self) -> Option<&'a [u8]> {
+        let len = self.u16()? as usize;
+        self.take(len)
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos == self.data.len()
+    }
+}
+
+/// Parse a TPMS_ATTEST structure produced by TPM2_Quote
+pub fn parse_tpms_attest(data: &[u8]) -> Option<TpmQuote> {
+    let mut reader = Reader::new(data);
+
+    if reader.u32()? != TPM_GENERATED_VALUE || reader.u16()? != TPM_ST_ATTEST_QUOTE {
+        return None;
+    }
+
+    let qualified_signer = reader.tpm2b()?.to_vec();
+    let extra_data = reader.tpm2b()?.to_vec();
+
+    // TPMS_CLOCK_INFO
+    let clock = reader.u64()?;
+    let reset_count = reader.u32()?;
+    let restart_count = reader.u32()?;
+    let safe = reader.u8()? != 0;
+
+    let firmware_version = reader.u64()?;
+
+    // TPMS_QUOTE_INFO: TPML_PCR_SELECTION followed by the PCR digest
+    let selection_count = reader.u32()?;
+    if selection_count > MAX_PCR_SELECTIONS {
+        return None;
+    }
+    let mut pcr_selections = Vec::with_capacity(selection_count as usize);
+    for _ in 0..selection_count {
+        let hash_alg = reader.u16()?;
+        let select_size = reader.u8()? as usize;
+        pcr_selections.push(PcrSelection {
+            hash_alg,
+            pcr_select: reader.take(select_size)?.to_vec(),
+        });
+    }
+    let pcr_digest = reader.tpm2b()?.to_vec();
+
+    if !reader.is_done() {
+        return None;
+    }
+
+    Some(TpmQuote {
+        qualified_signer,
+        extra_data,
+        clock,
+        reset_count,
+        restart_count,
+        safe,
+        firmware_version,
+        pcr_selections,
+        pcr_digest,
+    })
+}
+
+/// Parse a TPMT_SIGNATURE carrying an EdDSA (Ed25519) or ECDSA (P-256) signature.
+/// ECDSA components may be shorter than 32 bytes; S is made low for the secp256r1
+/// precompile, which only accepts low-S signatures. (R, n - S) is equally valid.
+pub fn parse_signature(data: &[u8]) -> Option<TpmSignature> {
+    let mut reader = Reader::new(data);
+
+    let sig_alg = reader.u16()?;
+    let hash_alg = reader.u16()?;
+    let r = reader.tpm2b()?;
+    let s = reader.tpm2b()?;
+    if !reader.is_done() {
+        return None;
+    }
+
+    let mut signature = [0u8; 64];
+    match sig_alg {
+        TPM_ALG_EDDSA if r.len() == 32 && s.len() == 32 => {
+            signature[..32].copy_from_slice(r);
+            signature[32..].copy_from_slice(s);
+        }
+        TPM_ALG_ECDSA if r.len() <= 32 && s.len() <= 32 => {
+            signature[32 - r.len()..32].copy_from_slice(r);
+            signature[64 - s.len()..].copy_from_slice(s);
+            let s: &mut [u8; 32] = (&mut signature[32..]).try_into().unwrap();
+            if is_high_s(s) {
+                *s = p256_order_minus(s);
+            }
+        }
+        _ => return None,
+    }
+
+    Some(TpmSignature {
+        sig_alg,
+        hash_alg,
+        signature,
+    })
+}
+
+/// Whether `s` is above half the P-256 order
+fn is_high_s(s: &[u8; 32]) -> bool {
+    // n / 2, rounded down
+    let mut half_order = [0u8; 32];
+    let mut carry = 0u8;
+    for (half, byte) in half_order.iter_mut().zip(P256_ORDER) {
+        *half = (carry << 7) | (byte >> 1);
+        carry = byte & 1;
+    }
+    s > &half_order
+}
+
+/// n - s for the P-256 order n, with s < n
+fn p256_order_minus(s: &[u8; 32]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut difference = P256_ORDER[i] as i16 - s[i] as i16 - borrow;
+        borrow = (difference < 0) as i16;
+        if difference < 0 {
+            difference += 256;
+        }
+        result[i] = difference as u8;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generated by testdata/generate_tpm.py: TPM2_Quote layouts signed with software keys
+    const QUOTE: &[u8] = include_bytes!("../testdata/tpm_quote.bin");
+    const QUOTE_SHA1_BANK: &[u8] = include_bytes!("../testdata/tpm_quote_sha1_bank.bin");
+    const ECDSA_SIGNATURE: &[u8] = include_bytes!("../testdata/tpm_signature_ecdsa.bin");
+    const ECDSA_SIGNATURE_HIGH_S: &[u8] = include_bytes!("../testdata/tpm_signature_ecdsa_high_s.bin");
+    const EDDSA_SIGNATURE: &[u8] = include_bytes!("../testdata/tpm_signature_eddsa.bin");
+
+    const ECDSA_R: &str = "20bd7b2745bc74dd8a92ce2f3f357309f83b2d448e37ab7abe949dcd30273d8f";
+    const ECDSA_LOW_S: &str = "2d611b968c020c64ea552ce5002cf09f6b8e5805d7eb11f6c87eda4ce25eb8f5";
+
+    fn hex32(hex: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn parses_quote() {
+        let quote = parse_tpms_attest(QUOTE).unwrap();
+
+        assert_eq!(quote.extra_data, [0x42; 32]);
+        assert_eq!(quote.clock, 1_000_000);
+        assert_eq!(quote.reset_count, 3);
+        assert!(quote.safe);
+        assert_eq!(
+            quote.pcr_selections,
+            vec![PcrSelection { hash_alg: TPM_ALG_SHA256, pcr_select: vec![0xff, 0x00, 0x00] }]
+        );
+        assert_eq!(
+            quote.sha256_pcr_digest(),
+            Some(hex32("db05e55d6193247cd749ccc4bcecd7efa1aaeda3e11093a042c9f97957bc16d7"))
+        );
+    }
+
+    #[test]
+    fn quote_without_sha256_bank_has_no_digest() {
+        let quote = parse_tpms_attest(QUOTE_SHA1_BANK).unwrap();
+
+        assert!(!quote.selects_sha256_pcrs());
+        assert_eq!(quote.sha256_pcr_digest(), None);
+    }
+
+    #[test]
+    fn rejects_trailing_bytes() {
+        let mut data = QUOTE.to_vec();
+        data.push(0);
+        assert_eq!(parse_tpms_attest(&data), None);
+
+        let mut data = ECDSA_SIGNATURE.to_vec();
+        data.push(0);
+        assert_eq!(parse_signature(&data), None);
+    }
+
+    #[test]
+    fn rejects_truncated_quote() {
+        for len in [0, 4, 6, QUOTE.len() / 2, QUOTE.len() - 1] {
+            assert_eq!(parse_tpms_attest(&QUOTE[..len]), None, "truncated to {len} bytes");
+        }
+    }
+
+    #[test]
+    fn rejects_other_structure_types() {
+        let mut data = QUOTE.to_vec();
+        data[5] = 0x17; // TPM_ST_ATTEST_CERTIFY
+        assert_eq!(parse_tpms_attest(&data), None);
+    }
+
+    #[test]
+    fn parses_ecdsa_signature() {
+        let signature = parse_signature(ECDSA_SIGNATURE).unwrap();
+
+        assert_eq!(signature.sig_alg, TPM_ALG_ECDSA);
+        assert_eq!(signature.hash_alg, TPM_ALG_SHA256);
+        assert_eq!(signature.signature[..32], hex32(ECDSA_R));
+        assert_eq!(signature.signature[32..], hex32(ECDSA_LOW_S));
+    }
+
+    #[test]
+    fn normalizes_high_s_ecdsa_signature() {
+        let signature = parse_signature(ECDSA_SIGNATURE_HIGH_S).unwrap();
+
+        assert_eq!(signature.signature[32..], hex32(ECDSA_LOW_S));
+    }
+
+    #[test]
+    fn pads_short_ecdsa_components() {
+        // R with its leading byte stripped, as some TPMs return it
+        let mut data = vec![0x00, 0x18, 0x00, 0x0b, 0x00, 31];
+        data.extend_from_slice(&[0x11; 31]);
+        data.extend_from_slice(&[0x00, 32]);
+        data.extend_from_slice(&[0x22; 32]);
+
+        let signature = parse_signature(&data).unwrap();
+        assert_eq!(signature.signature[0], 0);
+        assert_eq!(signature.signature[1..32], [0x11; 31]);
+        assert_eq!(signature.signature[32..], [0x22; 32]);
+    }
+
+    #[test]
+    fn parses_eddsa_signature() {
+        let signature = parse_signature(EDDSA_SIGNATURE).unwrap();
+
+        assert_eq!(signature.sig_alg, TPM_ALG_EDDSA);
+        assert_eq!(signature.hash_alg, TPM_ALG_SHA256);
+    }
+
+    #[test]
+    fn rejects_unsupported_or_malformed_signatures() {
+        // RSASSA
+        let mut data = ECDSA_SIGNATURE.to_vec();
+        data[..2].copy_from_slice(&[0x00, 0x14]);
+        assert_eq!(parse_signature(&data), None);
+
+        // EdDSA components must be exactly 32 bytes
+        let data = [&[0x00, 0x60, 0x00, 0x0b, 0x00, 31][..], &[0x11; 31], &[0x00, 32], &[0x22; 32]].concat();
+        assert_eq!(parse_signature(&data), None);
+
+        // ECDSA components cannot be longer than the curve
+        let data = [&[0x00, 0x18, 0x00, 0x0b, 0x00, 33][..], &[0x11; 33], &[0x00, 32], &[0x22; 32]].concat();
+        assert_eq!(parse_signature(&data), None);
+    }
+}
@@ -0,0 +1,107 @@
+"""Generate the Android key attestation certificates used by the android.rs tests.
+
+These are self-signed stand-ins built with the same KeyDescription layout as keystore
+attestation certificates; they are not chained to the Google root and were not produced
+by a real device. Run from this directory with the `cryptography` package installed.
+"""
+
+import datetime
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec, ed25519, rsa
+from cryptography.x509.oid import NameOID
+
+KEY_DESCRIPTION_OID = x509.ObjectIdentifier("1.3.6.1.4.1.11129.2.1.17")
+
+TEE = 1
+STRONGBOX = 2
+
+
+def tlv(identifier: bytes, content: bytes) -> bytes:
+    if len(content) < 0x80:
+        length = bytes([len(content)])
+    else:
+        encoded = len(content).to_bytes((len(content).bit_length() + 7) // 8, "big")
+        length = bytes([0x80 | len(encoded)]) + encoded
+    return identifier + length + content
+
+
+def sequence(*parts: bytes) -> bytes:
+    return tlv(b"\x30", b"".join(parts))
+
+
+def integer(value: int, tag: bytes = b"\x02") -> bytes:
+    return tlv(tag, value.to_bytes(max(1, (value.bit_length() + 8) // 8), "big"))
+
+
+def enumerated(value: int) -> bytes:
+    return integer(value, b"\x0a")
+
+
+def octets(data: bytes) -> bytes:
+    return tlv(b"\x04", data)
+
+
+def root_of_trust(boot_state: int, boot_hash: bytes | None) -> bytes:
+    fields = [octets(bytes([0xB0]) * 32), tlv(b"\x01", b"\xff"), enumerated(boot_state)]
+    if boot_hash is not None:
+        fields.append(octets(boot_hash))
+    # [704] EXPLICIT, context-specific constructed, high tag number form
+    return tlv(b"\xbf\x85\x40", sequence(*fields))
+
+
+def key_description(version: int, security_level: int, challenge: bytes, hardware_enforced: bytes) -> bytes:
+    return sequence(
+        integer(version),
+        enumerated(security_level),
+        integer(version),
+        enumerated(security_level),
+        octets(challenge),
+        octets(b""),
+        sequence(),
+        sequence(hardware_enforced),
+    )
+
+
+def certificate(subject_key, extension: bytes) -> bytes:
+    issuer_key = ec.derive_private_key(0x5EED, ec.SECP256R1())
+    name = x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, "Android Keystore Key")])
+    builder = (
+        x509.CertificateBuilder()
+        .subject_name(name)
+        .issuer_name(x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, "Test Attestation CA")]))
+        .public_key(subject_key.public_key())
+        .serial_number(1)
+        .not_valid_before(datetime.datetime(2024, 1, 1))
+        .not_valid_after(datetime.datetime(2048, 1, 1))
+        .add_extension(x509.UnrecognizedExtension(KEY_DESCRIPTION_OID, extension), critical=False)
+    )
+    return builder.sign(issuer_key, hashes.SHA256()).public_bytes(serialization.Encoding.DER)
+
+
+CHALLENGE = bytes([0x42]) * 32
+BOOT_HASH = bytes([0xB1]) * 32
+
+vectors = {
+    # Attestation version 3, TEE, Ed25519 key
+    "android_tee_ed25519_v3.der": certificate(
+        ed25519.Ed25519PrivateKey.from_private_bytes(bytes([0x01]) * 32),
+        key_description(3, TEE, CHALLENGE, root_of_trust(0, BOOT_HASH)),
+    ),
+    # KeyMint 1.0 (attestation version 100), StrongBox, P-256 key
+    "android_strongbox_p256_v100.der": certificate(
+        ec.derive_private_key(0x02, ec.SECP256R1()),
+        key_description(100, STRONGBOX, CHALLENGE, root_of_trust(0, BOOT_HASH)),
+    ),
+    # Attestation version 4, TEE, RSA key (cannot sign on-chain)
+    "android_tee_rsa_v4.der": certificate(
+        rsa.generate_private_key(public_exponent=65537, key_size=1024),
+        key_description(4, TEE, CHALLENGE, root_of_trust(0, BOOT_HASH)),
+    ),
+}
+
+for name, der in vectors.items():
+    with open(name, "wb") as file:
+        file.write(der)
+    print(name, len(der))
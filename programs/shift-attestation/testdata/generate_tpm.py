@@ -0,0 +1,106 @@
+"""Generate the TPM 2.0 quote vectors used by the tpm.rs tests and tests/shift-attestation.ts.
+
+TPMS_ATTEST and TPMT_SIGNATURE are laid out as TPM2_Quote returns them (TPM 2.0 Library
+Part 2), but signed with software keys rather than by a physical TPM. The device
+certificates certify the EdDSA attestation key (or another key) under a test manufacturer
+Ed25519 key. Run from this directory with the `cryptography` package installed.
+"""
+
+import datetime
+import hashlib
+import struct
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec, ed25519
+from cryptography.hazmat.primitives.asymmetric.utils import decode_dss_signature
+from cryptography.x509.oid import NameOID
+
+TPM_GENERATED_VALUE = 0xFF544347
+TPM_ST_ATTEST_QUOTE = 0x8018
+TPM_ALG_SHA1 = 0x0004
+TPM_ALG_SHA256 = 0x000B
+TPM_ALG_ECDSA = 0x0018
+TPM_ALG_EDDSA = 0x0060
+
+P256_ORDER = 0xFFFFFFFF00000000FFFFFFFFFFFFFFFFBCE6FAADA7179E84F3B9CAC2FC632551
+
+NONCE = bytes([0x42]) * 32
+PCR_VALUES = [bytes([i]) * 32 for i in range(8)]
+
+
+def tpm2b(data: bytes) -> bytes:
+    return struct.pack(">H", len(data)) + data
+
+
+def tpms_attest(hash_alg: int, nonce: bytes = NONCE) -> bytes:
+    qualified_signer = struct.pack(">H", TPM_ALG_SHA256) + hashlib.sha256(b"attestation key").digest()
+    clock_info = struct.pack(">QIIB", 1_000_000, 3, 0, 1)
+    firmware_version = struct.pack(">Q", 0x0001_0002_0003_0004)
+    # PCRs 0-7 in one bank
+    pcr_selection = struct.pack(">I", 1) + struct.pack(">HB", hash_alg, 3) + bytes([0xFF, 0x00, 0x00])
+    pcr_digest = hashlib.sha256(b"".join(PCR_VALUES)).digest()
+    return (
+        struct.pack(">IH", TPM_GENERATED_VALUE, TPM_ST_ATTEST_QUOTE)
+        + tpm2b(qualified_signer)
+        + tpm2b(nonce)
+        + clock_info
+        + firmware_version
+        + pcr_selection
+        + tpm2b(pcr_digest)
+    )
+
+
+def tpmt_signature(sig_alg: int, r: bytes, s: bytes) -> bytes:
+    return struct.pack(">HH", sig_alg, TPM_ALG_SHA256) + tpm2b(r) + tpm2b(s)
+
+
+def device_certificate(subject_key: ed25519.Ed25519PrivateKey, serial: int) -> bytes:
+    builder = (
+        x509.CertificateBuilder()
+        .subject_name(x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, "Test TPM Attestation Key")]))
+        .issuer_name(x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, "Test TPM Manufacturer")]))
+        .public_key(subject_key.public_key())
+        .serial_number(serial)
+        .not_valid_before(datetime.datetime(2024, 1, 1))
+        .not_valid_after(datetime.datetime(2048, 1, 1))
+    )
+    return builder.sign(manufacturer_key, None).public_bytes(serialization.Encoding.DER)
+
+
+quote = tpms_attest(TPM_ALG_SHA256)
+
+ecdsa_key = ec.derive_private_key(0x03, ec.SECP256R1())
+r, s = decode_dss_signature(ecdsa_key.sign(quote, ec.ECDSA(hashes.SHA256(), deterministic_signing=True)))
+low_s = min(s, P256_ORDER - s)
+high_s = P256_ORDER - low_s
+
+eddsa_key = ed25519.Ed25519PrivateKey.from_private_bytes(bytes([0x04]) * 32)
+eddsa_signature = eddsa_key.sign(hashlib.sha256(quote).digest())
+
+manufacturer_key = ed25519.Ed25519PrivateKey.from_private_bytes(bytes([0x05]) * 32)
+other_key = ed25519.Ed25519PrivateKey.from_private_bytes(bytes([0x06]) * 32)
+
+vectors = {
+    "tpm_quote.bin": quote,
+    "tpm_quote_sha1_bank.bin": tpms_attest(TPM_ALG_SHA1),
+    "tpm_signature_ecdsa.bin": tpmt_signature(TPM_ALG_ECDSA, r.to_bytes(32, "big"), low_s.to_bytes(32, "big")),
+    "tpm_signature_ecdsa_high_s.bin": tpmt_signature(TPM_ALG_ECDSA, r.to_bytes(32, "big"), high_s.to_bytes(32, "big")),
+    "tpm_signature_eddsa.bin": tpmt_signature(TPM_ALG_EDDSA, eddsa_signature[:32], eddsa_signature[32:]),
+    "tpm_device_certificate.der": device_certificate(eddsa_key, 1),
+    "tpm_device_certificate_other_key.der": device_certificate(other_key, 2),
+}
+
+for name, data in vectors.items():
+    with open(name, "wb") as file:
+        file.write(data)
+    print(name, len(data))
+
+public_key = ecdsa_key.public_key().public_bytes(
+    serialization.Encoding.X962, serialization.PublicFormat.CompressedPoint
+)
+print("ecdsa key", public_key.hex())
+print("ecdsa r", r.to_bytes(32, "big").hex())
+print("ecdsa low s", low_s.to_bytes(32, "big").hex())
+print("eddsa key", eddsa_key.public_key().public_bytes(serialization.Encoding.Raw, serialization.PublicFormat.Raw).hex())
+print("pcr digest", hashlib.sha256(b"".join(PCR_VALUES)).hexdigest())
@@ -1,9 +1,24 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
 use borsh::{BorshDeserialize, BorshSerialize};
 use sha2::{Digest, Sha256, Sha512};
+use shift_attestation::program::ShiftAttestation;
+use shift_attestation::{
+    verify_ed25519_instruction, AttestationAuthority, AttestationRecord, AttestationStatus, AttestationValidity,
+};
+// Proofs are built off-chain with shift-key-proofs; the program only verifies them
+pub use shift_key_proofs::{destruction_commitment, destruction_message, destruction_use_tag, key_batch_message};
 
 declare_id!("ENCUMB111111111111111111111111111111111111");
 
+pub mod wots;
+
+/// Most key batches a pool can commit to; the pool account grows by one batch per replenish
+pub const MAX_KEY_BATCHES: usize = 64;
+
+/// Deepest key Merkle tree, enough for a batch of 2^24 keys
+pub const MAX_MERKLE_DEPTH: usize = 24;
+
 #[program]
 pub mod shift_encumbrance {
     use super::*;
@@ -20,97 +35,191 @@ pub mod shift_encumbrance {
         Ok(())
     }
 
-    /// Initialize key pool for a hardware device
+    /// Initialize key pool for a hardware device. The pool only stores the Merkle root
+    /// of the device's one-time public keys; each key is proven against it when used.
+    /// Only the owner of an attested device may create its pool, and the first batch
+    /// must be signed by the attested device key like any later one.
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_key_pool(
         ctx: Context<InitializeKeyPool>,
         device_id: [u8; 32],
-        initial_pool_size: u32,
-        public_keys: Vec<[u8; 32]>, // Initial set of public keys
+        merkle_root: [u8; 32], // Root over the first batch of public keys
+        key_count: u32,
+        key_scheme: KeyScheme,
+        wots_public_seed: [u8; 32], // Only used by WOTS+ pools
+        key_usage_policy: KeyUsagePolicy,
+        batch_signature: [u8; 64], // Attested device key's signature over the batch
     ) -> Result<()> {
         let key_pool = &mut ctx.accounts.key_pool;
         let encumbrance_authority = &mut ctx.accounts.encumbrance_authority;
+        let attestation_record = &ctx.accounts.attestation_record;
 
         require!(
-            public_keys.len() <= initial_pool_size as usize,
+            key_count > 0 && key_count <= 1 << MAX_MERKLE_DEPTH,
             EncumbranceError::InvalidPoolSize
         );
 
+        verify_device_attestation(
+            &ctx.accounts.attestation_program,
+            attestation_record,
+            &ctx.accounts.manufacturer_account,
+            &ctx.accounts.measurement_deprecation,
+            &ctx.accounts.crl_bucket,
+            device_id,
+        )?;
+        require!(
+            attestation_record.device_owner == ctx.accounts.owner.key(),
+            EncumbranceError::UnauthorizedOwner
+        );
+        require!(
+            attestation_record.verify_device_signature(
+                &ctx.accounts.instructions,
+                &key_batch_message(&device_id, &merkle_root, 0, key_count),
+                &batch_signature,
+            )?,
+            EncumbranceError::InvalidBatchSignature
+        );
+
         key_pool.device_id = device_id;
         key_pool.owner = ctx.accounts.owner.key();
-        key_pool.total_keys = initial_pool_size;
-        key_pool.available_keys = public_keys.len() as u32;
+        key_pool.total_keys = key_count;
+        key_pool.available_keys = key_count;
         key_pool.used_keys = 0;
-        key_pool.public_keys = public_keys;
-        key_pool.encumbered_keys = Vec::new();
+        key_pool.key_scheme = key_scheme;
+        key_pool.wots_public_seed = wots_public_seed;
+        key_pool.frozen = false;
+        key_pool.key_usage_policy = key_usage_policy;
+        key_pool.next_key_index = 0;
+        key_pool.key_batches = vec![KeyBatch {
+            merkle_root,
+            first_index: 0,
+            key_count,
+        }];
         key_pool.created_at = Clock::get()?.unix_timestamp;
         key_pool.bump = ctx.bumps.key_pool;
 
         encumbrance_authority.total_devices += 1;
 
-        msg!("Key pool initialized for device: {:?} with {} keys", device_id, initial_pool_size);
+        msg!("Key pool initialized for device: {:?} with {} keys", device_id, key_count);
         Ok(())
     }
 
-    /// Register a key usage and create encumbrance proof
+    /// Register a key usage and create encumbrance proof. `merkle_proof` holds the
+    /// sibling hashes from the key's leaf up to its batch root.
+    #[allow(clippy::too_many_arguments)]
     pub fn encumber_key(
         ctx: Context<EncumberKey>,
         device_id: [u8; 32],
         key_index: u32,
         public_key: [u8; 32],
+        merkle_proof: Vec<[u8; 32]>,
         destruction_proof: KeyDestructionProof,
         transaction_hash: [u8; 32],
+        counterparty: Pubkey, // Party the key is used with; only it may mark the encumbrance verified
     ) -> Result<()> {
-        let key_pool = &mut ctx.accounts.key_pool;
-        let encumbrance_record = &mut ctx.accounts.encumbrance_record;
-        let encumbrance_authority = &mut ctx.accounts.encumbrance_authority;
-
-        // Verify device ID matches
         require!(
-            key_pool.device_id == device_id,
-            EncumbranceError::DeviceIdMismatch
+            ctx.accounts.key_pool.key_scheme == KeyScheme::Standard,
+            EncumbranceError::KeySchemeMismatch
         );
 
-        // Verify key exists in pool and hasn't been used
-        require!(
-            key_index < key_pool.public_keys.len() as u32,
-            EncumbranceError::InvalidKeyIndex
-        );
+        // Hardware proofs are signed by the key the device attested to
+        if let Some(attestation_record) = &ctx.accounts.attestation_record {
+            let accounts = &ctx.accounts;
+            verify_device_attestation(
+                accounts.attestation_program.as_ref().ok_or(EncumbranceError::AttestationAccountsMissing)?,
+                attestation_record,
+                accounts.manufacturer_account.as_ref().ok_or(EncumbranceError::AttestationAccountsMissing)?,
+                accounts.measurement_deprecation.as_ref().ok_or(EncumbranceError::AttestationAccountsMissing)?,
+                accounts.crl_bucket.as_ref().ok_or(EncumbranceError::AttestationAccountsMissing)?,
+                device_id,
+            )?;
+        }
+        let attestation_record = ctx.accounts.attestation_record.as_deref();
 
+        // Verify destruction proof
         require!(
-            key_pool.public_keys[key_index as usize] == public_key,
-            EncumbranceError::KeyMismatch
+            verify_destruction_proof(
+                &destruction_proof,
+                &ctx.accounts.key_pool,
+                key_index,
+                &public_key,
+                &merkle_proof,
+                &transaction_hash,
+                attestation_record,
+                &ctx.accounts.instructions,
+            )?,
+            EncumbranceError::InvalidDestructionProof
         );
 
-        // Check if key is already encumbered
+        ctx.accounts.encumber(
+            &ctx.bumps,
+            device_id,
+            key_index,
+            public_key,
+            &merkle_proof,
+            destruction_proof,
+            transaction_hash,
+            counterparty,
+        )?;
+
+        msg!("Key encumbered: device {:?}, key index {}", device_id, key_index);
+        Ok(())
+    }
+
+    /// Encumber a key from a WOTS+ pool. The one-time signature over `transaction_hash`
+    /// is itself the proof of use: it must recover the committed public key.
+    #[allow(clippy::too_many_arguments)]
+    pub fn encumber_key_wots(
+        ctx: Context<EncumberKey>,
+        device_id: [u8; 32],
+        key_index: u32,
+        public_key: [u8; 32],
+        merkle_proof: Vec<[u8; 32]>,
+        wots_signature: wots::WotsSignature,
+        transaction_hash: [u8; 32],
+        counterparty: Pubkey,
+    ) -> Result<()> {
+        let key_pool = &ctx.accounts.key_pool;
+
         require!(
-            !key_pool.encumbered_keys.contains(&key_index),
-            EncumbranceError::KeyAlreadyEncumbered
+            key_pool.key_scheme == KeyScheme::WotsPlus,
+            EncumbranceError::KeySchemeMismatch
         );
-
-        // Verify destruction proof
         require!(
-            verify_destruction_proof(&destruction_proof, &public_key, &transaction_hash),
+            wots::public_key_from_signature(
+                &key_pool.wots_public_seed,
+                key_index,
+                &transaction_hash,
+                &wots_signature,
+            ) == public_key,
             EncumbranceError::InvalidDestructionProof
         );
 
-        // Create encumbrance record
-        encumbrance_record.device_id = device_id;
-        encumbrance_record.key_index = key_index;
-        encumbrance_record.public_key = public_key;
-        encumbrance_record.transaction_hash = transaction_hash;
-        encumbrance_record.destruction_proof = destruction_proof;
-        encumbrance_record.encumbered_at = Clock::get()?.unix_timestamp;
-        encumbrance_record.status = EncumbranceStatus::Encumbered;
-        encumbrance_record.bump = ctx.bumps.encumbrance_record;
+        // The signature is in the transaction; the record keeps a digest of it
+        let mut proof_data = [0u8; 256];
+        let chain_values: Vec<&[u8]> = wots_signature.iter().map(|value| value.as_slice()).collect();
+        proof_data[..32].copy_from_slice(&shift_key_proofs::hashv(&chain_values));
 
-        // Update key pool
-        key_pool.encumbered_keys.push(key_index);
-        key_pool.used_keys += 1;
-        key_pool.available_keys -= 1;
+        let destruction_proof = KeyDestructionProof {
+            proof_type: ProofType::WotsSignature,
+            proof_data,
+            timestamp: Clock::get()?.unix_timestamp,
+            nonce: [0u8; 32],
+            hardware_signature: [0u8; 64],
+        };
 
-        encumbrance_authority.total_encumbered_keys += 1;
+        ctx.accounts.encumber(
+            &ctx.bumps,
+            device_id,
+            key_index,
+            public_key,
+            &merkle_proof,
+            destruction_proof,
+            transaction_hash,
+            counterparty,
+        )?;
 
-        msg!("Key encumbered: device {:?}, key index {}", device_id, key_index);
+        msg!("WOTS+ key encumbered: device {:?}, key index {}", device_id, key_index);
         Ok(())
     }
 
@@ -139,8 +248,9 @@ pub mod shift_encumbrance {
             EncumbranceError::TransactionHashMismatch
         );
 
+        // Disputed encumbrances are no longer relied on
         require!(
-            encumbrance_record.status == EncumbranceStatus::Encumbered,
+            encumbrance_record.status != EncumbranceStatus::Disputed,
             EncumbranceError::KeyNotEncumbered
         );
 
@@ -148,11 +258,13 @@ pub mod shift_encumbrance {
         Ok(true)
     }
 
-    /// Add new keys to an existing key pool
+    /// Add a new batch of keys, committed to by its Merkle root, to an existing key pool
     pub fn replenish_key_pool(
         ctx: Context<ReplenishKeyPool>,
         device_id: [u8; 32],
-        new_public_keys: Vec<[u8; 32]>,
+        merkle_root: [u8; 32],
+        key_count: u32,
+        batch_signature: [u8; 64], // Attested device key's signature over the batch
     ) -> Result<()> {
         let key_pool = &mut ctx.accounts.key_pool;
 
@@ -165,46 +277,197 @@ pub mod shift_encumbrance {
             key_pool.owner == ctx.accounts.owner.key(),
             EncumbranceError::UnauthorizedOwner
         );
+        require!(!key_pool.frozen, EncumbranceError::KeyPoolFrozen);
 
-        // Add new keys to the pool
-        for new_key in new_public_keys.iter() {
-            key_pool.public_keys.push(*new_key);
-        }
+        require!(
+            key_count > 0 && key_count <= 1 << MAX_MERKLE_DEPTH,
+            EncumbranceError::InvalidPoolSize
+        );
+        require!(
+            key_pool.key_batches.len() < MAX_KEY_BATCHES,
+            EncumbranceError::KeyPoolFull
+        );
+        require!(
+            !key_pool
+                .key_batches
+                .iter()
+                .any(|batch| batch.merkle_root == merkle_root),
+            EncumbranceError::DuplicateKeyBatch
+        );
+
+        // New keys continue the pool's index space
+        let first_index = key_pool.total_keys;
+        let total_keys = first_index
+            .checked_add(key_count)
+            .ok_or(EncumbranceError::InvalidPoolSize)?;
 
-        key_pool.total_keys += new_public_keys.len() as u32;
-        key_pool.available_keys += new_public_keys.len() as u32;
+        // Only the secure hardware may add keys, so the batch must be signed by the attested key
+        let attestation_record = &ctx.accounts.attestation_record;
+        verify_device_attestation(
+            &ctx.accounts.attestation_program,
+            attestation_record,
+            &ctx.accounts.manufacturer_account,
+            &ctx.accounts.measurement_deprecation,
+            &ctx.accounts.crl_bucket,
+            device_id,
+        )?;
+        require!(
+            attestation_record.verify_device_signature(
+                &ctx.accounts.instructions,
+                &key_batch_message(&device_id, &merkle_root, first_index, key_count),
+                &batch_signature,
+            )?,
+            EncumbranceError::InvalidBatchSignature
+        );
 
-        msg!("Key pool replenished: {} new keys added", new_public_keys.len());
+        key_pool.key_batches.push(KeyBatch {
+            merkle_root,
+            first_index,
+            key_count,
+        });
+        key_pool.total_keys = total_keys;
+        key_pool.available_keys += key_count;
+
+        msg!("Key pool replenished: {} new keys added", key_count);
         Ok(())
     }
 
-    /// Generate a zero-knowledge proof of key destruction
-    pub fn create_destruction_proof(
-        ctx: Context<CreateDestructionProof>,
+    /// Record that the counterparty named at encumbrance time has checked it off-chain
+    pub fn mark_encumbrance_verified(
+        ctx: Context<MarkEncumbranceVerified>,
         device_id: [u8; 32],
-        private_key_hash: [u8; 32], // Hash of the private key (never store the actual key)
-        public_key: [u8; 32],
-        nonce: [u8; 32],
-    ) -> Result<KeyDestructionProof> {
-        // In a real implementation, this would generate a ZK-SNARK proof
-        // For demo purposes, we'll create a simplified proof structure
-        let proof_data = create_destruction_proof_data(
-            &device_id,
-            &private_key_hash,
-            &public_key,
-            &nonce,
+        key_index: u32,
+    ) -> Result<()> {
+        let encumbrance_record = &mut ctx.accounts.encumbrance_record;
+        let counterparty = ctx.accounts.counterparty.key();
+
+        require!(
+            counterparty == encumbrance_record.counterparty,
+            EncumbranceError::UnauthorizedCounterparty
+        );
+        require!(
+            encumbrance_record.status == EncumbranceStatus::Encumbered,
+            EncumbranceError::InvalidEncumbranceStatus
+        );
+
+        encumbrance_record.status = EncumbranceStatus::Verified;
+        encumbrance_record.verified_by = Some(counterparty);
+
+        msg!("Key encumbrance verified by {}: device {:?}, key {}", counterparty, device_id, key_index);
+        Ok(())
+    }
+
+    /// Dispute an encumbrance with proof that its key signed two different transaction
+    /// hashes. A proven double-sign freezes the device's key pool and flags the device's
+    /// attestation, so it fails until governance makes this program the fraud reporter.
+    pub fn dispute_encumbrance(
+        ctx: Context<DisputeEncumbrance>,
+        device_id: [u8; 32],
+        key_index: u32,
+        evidence: DoubleSignEvidence,
+    ) -> Result<()> {
+        let key_pool = &mut ctx.accounts.key_pool;
+        let encumbrance_record = &mut ctx.accounts.encumbrance_record;
+
+        require!(
+            encumbrance_record.status != EncumbranceStatus::Disputed,
+            EncumbranceError::InvalidEncumbranceStatus
+        );
+        require!(
+            verify_double_sign(
+                key_pool,
+                key_index,
+                &encumbrance_record.public_key,
+                &evidence,
+                Some(&encumbrance_record.transaction_hash),
+                &ctx.accounts.instructions,
+            )?,
+            EncumbranceError::InvalidDoubleSignProof
+        );
+
+        encumbrance_record.status = EncumbranceStatus::Disputed;
+        key_pool.frozen = true;
+
+        let attestation_flagged = report_device_fraud(
+            &ctx.accounts.attestation_program,
+            &ctx.accounts.encumbrance_authority,
+            &ctx.accounts.attestation_authority,
+            &ctx.accounts.attestation_record,
+            device_id,
         )?;
 
-        let destruction_proof = KeyDestructionProof {
-            proof_type: ProofType::ZeroKnowledge,
-            proof_data,
-            timestamp: Clock::get()?.unix_timestamp,
-            nonce,
-            hardware_signature: [0u8; 64], // Would be signed by hardware device
+        emit!(DoubleSignProven {
+            device_id,
+            key_index,
+            public_key: encumbrance_record.public_key,
+            attestation_flagged,
+            proven_at: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Key encumbrance disputed: device {:?}, key {} double-signed", device_id, key_index);
+        Ok(())
+    }
+
+    /// Prove that any key in the pool, used through the protocol or not, signed two
+    /// different transaction hashes. Freezes the key pool and flags the device's
+    /// attestation; revoking it is left to governance. Keys committed with a destruction
+    /// commitment are located by passing that `commitment`.
+    pub fn submit_double_sign_proof(
+        ctx: Context<SubmitDoubleSignProof>,
+        device_id: [u8; 32],
+        key_index: u32,
+        public_key: [u8; 32],
+        merkle_proof: Vec<[u8; 32]>,
+        commitment: Option<[u8; 32]>,
+        evidence: DoubleSignEvidence,
+    ) -> Result<()> {
+        let key_pool = &mut ctx.accounts.key_pool;
+
+        // Verify key exists in pool, under whichever leaf kind it was committed with
+        let key_batch = key_pool
+            .find_batch(key_index)
+            .ok_or(EncumbranceError::InvalidKeyIndex)?;
+        let leaf = match commitment {
+            Some(commitment) => key_commitment_leaf_hash(&public_key, &commitment),
+            None => key_leaf_hash(&public_key),
         };
+        require!(
+            key_batch.contains_leaf(key_index, &leaf, &merkle_proof),
+            EncumbranceError::KeyMismatch
+        );
+
+        require!(
+            verify_double_sign(
+                key_pool,
+                key_index,
+                &public_key,
+                &evidence,
+                None,
+                &ctx.accounts.instructions,
+            )?,
+            EncumbranceError::InvalidDoubleSignProof
+        );
+
+        key_pool.frozen = true;
+
+        let attestation_flagged = report_device_fraud(
+            &ctx.accounts.attestation_program,
+            &ctx.accounts.encumbrance_authority,
+            &ctx.accounts.attestation_authority,
+            &ctx.accounts.attestation_record,
+            device_id,
+        )?;
 
-        msg!("Destruction proof created for key: {:?}", public_key);
-        Ok(destruction_proof)
+        emit!(DoubleSignProven {
+            device_id,
+            key_index,
+            public_key,
+            attestation_flagged,
+            proven_at: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Double-sign proven: device {:?}, key {}; key pool frozen", device_id, key_index);
+        Ok(())
     }
 }
 
@@ -235,7 +498,7 @@ pub struct InitializeKeyPool<'info> {
     #[account(
         init,
         payer = owner,
-        space = 8 + KeyPool::LEN,
+        space = KeyPool::space(1),
         seeds = [b"key_pool", device_id.as_ref()],
         bump
     )]
@@ -247,12 +510,35 @@ pub struct InitializeKeyPool<'info> {
         bump = encumbrance_authority.bump
     )]
     pub encumbrance_authority: Account<'info, EncumbranceAuthority>,
+
+    /// Device's attestation, whose key must sign the first batch
+    #[account(
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump,
+        seeds::program = shift_attestation::ID
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    /// CHECK: Manufacturer of the attested device; checked by `verify_attestation`
+    pub manufacturer_account: UncheckedAccount<'info>,
+
+    /// CHECK: Deprecation marker for the record's measurement set; checked by `verify_attestation`
+    pub measurement_deprecation: UncheckedAccount<'info>,
+
+    /// CHECK: CRL bucket for the record's certificate serial; checked by `verify_attestation`
+    pub crl_bucket: UncheckedAccount<'info>,
+
+    pub attestation_program: Program<'info, ShiftAttestation>,
+
+    /// CHECK: Instructions sysvar, read to find the Ed25519 program instruction
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(device_id: [u8; 32], key_index: u32)]
+#[instruction(device_id: [u8; 32], key_index: u32, public_key: [u8; 32])]
 pub struct EncumberKey<'info> {
     #[account(mut)]
     pub device_owner: Signer<'info>,
@@ -265,14 +551,36 @@ pub struct EncumberKey<'info> {
     )]
     pub key_pool: Account<'info, KeyPool>,
     
+    /// AnyOrder pools only; Sequential pools track use with `next_key_index` alone
     #[account(
-        init,
+        init_if_needed,
+        payer = device_owner,
+        space = 8 + KeyBitmapPage::LEN,
+        seeds = [b"key_bitmap", device_id.as_ref(), &KeyBitmapPage::page_of(key_index).to_le_bytes()],
+        bump
+    )]
+    pub key_bitmap: Option<Account<'info, KeyBitmapPage>>,
+    
+    #[account(
+        init_if_needed,
         payer = device_owner,
         space = 8 + EncumbranceRecord::LEN,
         seeds = [b"encumbrance", device_id.as_ref(), &key_index.to_le_bytes()],
         bump
     )]
     pub encumbrance_record: Account<'info, EncumbranceRecord>,
+
+    /// One per device and public key, so a key committed in more than one of the
+    /// device's batches can still only be used once. Scoped to the device so other
+    /// devices cannot claim its keys first.
+    #[account(
+        init_if_needed,
+        payer = device_owner,
+        space = 8 + KeyNullifier::LEN,
+        seeds = [b"key_nullifier", device_id.as_ref(), public_key.as_ref()],
+        bump
+    )]
+    pub key_nullifier: Account<'info, KeyNullifier>,
     
     #[account(
         mut,
@@ -280,10 +588,144 @@ pub struct EncumberKey<'info> {
         bump = encumbrance_authority.bump
     )]
     pub encumbrance_authority: Account<'info, EncumbranceAuthority>,
+
+    /// Device's attestation, needed for HardwareAttestation proofs
+    #[account(
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump,
+        seeds::program = shift_attestation::ID
+    )]
+    pub attestation_record: Option<Account<'info, AttestationRecord>>,
+
+    /// CHECK: Manufacturer of the attested device; checked by `verify_attestation`
+    pub manufacturer_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Deprecation marker for the record's measurement set; checked by `verify_attestation`
+    pub measurement_deprecation: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: CRL bucket for the record's certificate serial; checked by `verify_attestation`
+    pub crl_bucket: Option<UncheckedAccount<'info>>,
+
+    pub attestation_program: Option<Program<'info, ShiftAttestation>>,
+
+    /// CHECK: Instructions sysvar, read to find the Ed25519 program instruction
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> EncumberKey<'info> {
+    /// Check that `public_key` is the pool's unused key at `key_index`, mark it used
+    /// and record the encumbrance
+    #[allow(clippy::too_many_arguments)]
+    fn encumber(
+        &mut self,
+        bumps: &EncumberKeyBumps,
+        device_id: [u8; 32],
+        key_index: u32,
+        public_key: [u8; 32],
+        merkle_proof: &[[u8; 32]],
+        destruction_proof: KeyDestructionProof,
+        transaction_hash: [u8; 32],
+        counterparty: Pubkey,
+    ) -> Result<()> {
+        let key_pool = &mut self.key_pool;
+        let encumbrance_record = &mut self.encumbrance_record;
+
+        // Verify device ID matches
+        require!(
+            key_pool.device_id == device_id,
+            EncumbranceError::DeviceIdMismatch
+        );
+        require!(!key_pool.frozen, EncumbranceError::KeyPoolFrozen);
+
+        // The device owner cannot vouch for its own encumbrance
+        require!(
+            counterparty != key_pool.owner,
+            EncumbranceError::UnauthorizedCounterparty
+        );
+
+        // Verify key exists in pool and hasn't been used
+        let key_batch = key_pool
+            .find_batch(key_index)
+            .ok_or(EncumbranceError::InvalidKeyIndex)?;
+
+        require!(
+            key_batch.contains_leaf(key_index, &destruction_proof.key_leaf(&public_key), merkle_proof),
+            EncumbranceError::KeyMismatch
+        );
+
+        let current_time = Clock::get()?.unix_timestamp;
+
+        // The nullifier rules out reusing a public key committed in more than one
+        // batch, whatever the pool's usage policy. It may have just been created.
+        let key_nullifier = &mut self.key_nullifier;
+        require!(
+            key_nullifier.encumbered_at == 0,
+            EncumbranceError::KeyAlreadyEncumbered
+        );
+
+        let skipped_keys = match key_pool.key_usage_policy {
+            // Sequential pools only move forward, so the high-water mark stands in
+            // for the bitmap; keys skipped over can no longer be used
+            KeyUsagePolicy::Sequential => {
+                require!(
+                    self.key_bitmap.is_none(),
+                    EncumbranceError::KeyUsageAccountsMismatch
+                );
+                require!(
+                    key_index >= key_pool.next_key_index,
+                    EncumbranceError::KeyOutOfSequence
+                );
+                key_index - key_pool.next_key_index
+            }
+            // Check if the index is already encumbered. The bitmap page may have
+            // just been created.
+            KeyUsagePolicy::AnyOrder => {
+                let (Some(key_bitmap), Some(bitmap_bump)) = (self.key_bitmap.as_mut(), bumps.key_bitmap) else {
+                    return err!(EncumbranceError::KeyUsageAccountsMismatch);
+                };
+
+                key_bitmap.device_id = device_id;
+                key_bitmap.page = KeyBitmapPage::page_of(key_index);
+                key_bitmap.bump = bitmap_bump;
+                require!(
+                    !key_bitmap.is_set(key_index),
+                    EncumbranceError::KeyAlreadyEncumbered
+                );
+                key_bitmap.set(key_index);
+                0
+            }
+        };
+
+        key_nullifier.device_id = device_id;
+        key_nullifier.key_index = key_index;
+        key_nullifier.encumbered_at = current_time;
+        key_nullifier.bump = bumps.key_nullifier;
+
+        // Create encumbrance record
+        encumbrance_record.device_id = device_id;
+        encumbrance_record.key_index = key_index;
+        encumbrance_record.public_key = public_key;
+        encumbrance_record.transaction_hash = transaction_hash;
+        encumbrance_record.counterparty = counterparty;
+        encumbrance_record.destruction_proof = destruction_proof;
+        encumbrance_record.encumbered_at = current_time;
+        encumbrance_record.status = EncumbranceStatus::Encumbered;
+        encumbrance_record.verified_by = None;
+        encumbrance_record.bump = bumps.encumbrance_record;
+
+        // Update key pool
+        key_pool.used_keys += 1;
+        key_pool.available_keys -= 1 + skipped_keys;
+        key_pool.next_key_index = key_pool.next_key_index.max(key_index + 1);
+
+        self.encumbrance_authority.total_encumbered_keys += 1;
+        Ok(())
+    }
+}
+
 #[derive(Accounts)]
 #[instruction(device_id: [u8; 32], key_index: u32)]
 pub struct VerifyEncumbrance<'info> {
@@ -304,14 +746,143 @@ pub struct ReplenishKeyPool<'info> {
         mut,
         seeds = [b"key_pool", device_id.as_ref()],
         bump = key_pool.bump,
-        constraint = key_pool.owner == owner.key()
+        constraint = key_pool.owner == owner.key(),
+        realloc = KeyPool::space(key_pool.key_batches.len() + 1),
+        realloc::payer = owner,
+        realloc::zero = false
     )]
     pub key_pool: Account<'info, KeyPool>,
+
+    /// Device's attestation, whose key must sign the new batch
+    #[account(
+        seeds = [b"attestation", device_id.as_ref()],
+        bump = attestation_record.bump,
+        seeds::program = shift_attestation::ID
+    )]
+    pub attestation_record: Account<'info, AttestationRecord>,
+
+    /// CHECK: Manufacturer of the attested device; checked by `verify_attestation`
+    pub manufacturer_account: UncheckedAccount<'info>,
+
+    /// CHECK: Deprecation marker for the record's measurement set; checked by `verify_attestation`
+    pub measurement_deprecation: UncheckedAccount<'info>,
+
+    /// CHECK: CRL bucket for the record's certificate serial; checked by `verify_attestation`
+    pub crl_bucket: UncheckedAccount<'info>,
+
+    pub attestation_program: Program<'info, ShiftAttestation>,
+
+    /// CHECK: Instructions sysvar, read to find the Ed25519 program instruction
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CreateDestructionProof<'info> {
-    pub signer: Signer<'info>,
+#[instruction(device_id: [u8; 32], key_index: u32)]
+pub struct MarkEncumbranceVerified<'info> {
+    pub counterparty: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"encumbrance", device_id.as_ref(), &key_index.to_le_bytes()],
+        bump = encumbrance_record.bump
+    )]
+    pub encumbrance_record: Account<'info, EncumbranceRecord>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32], key_index: u32)]
+pub struct DisputeEncumbrance<'info> {
+    pub disputer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"key_pool", device_id.as_ref()],
+        bump = key_pool.bump
+    )]
+    pub key_pool: Account<'info, KeyPool>,
+
+    #[account(
+        mut,
+        seeds = [b"encumbrance", device_id.as_ref(), &key_index.to_le_bytes()],
+        bump = encumbrance_record.bump
+    )]
+    pub encumbrance_record: Account<'info, EncumbranceRecord>,
+
+    /// Signs the CPI that flags the device's attestation
+    #[account(
+        seeds = [b"encumbrance_authority"],
+        bump = encumbrance_authority.bump
+    )]
+    pub encumbrance_authority: Account<'info, EncumbranceAuthority>,
+
+    /// Read to check that governance lets this program report fraud
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        seeds::program = shift_attestation::ID
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+
+    /// CHECK: Device's attestation, if any; flagged through shift-attestation
+    #[account(
+        mut,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump,
+        seeds::program = shift_attestation::ID
+    )]
+    pub attestation_record: UncheckedAccount<'info>,
+
+    pub attestation_program: Program<'info, ShiftAttestation>,
+
+    /// CHECK: Instructions sysvar, read to find the Ed25519 program instructions
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(device_id: [u8; 32])]
+pub struct SubmitDoubleSignProof<'info> {
+    pub submitter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"key_pool", device_id.as_ref()],
+        bump = key_pool.bump
+    )]
+    pub key_pool: Account<'info, KeyPool>,
+
+    /// Signs the CPI that flags the device's attestation
+    #[account(
+        seeds = [b"encumbrance_authority"],
+        bump = encumbrance_authority.bump
+    )]
+    pub encumbrance_authority: Account<'info, EncumbranceAuthority>,
+
+    /// Read to check that governance lets this program report fraud
+    #[account(
+        seeds = [b"attestation_authority"],
+        bump = attestation_authority.bump,
+        seeds::program = shift_attestation::ID
+    )]
+    pub attestation_authority: Account<'info, AttestationAuthority>,
+
+    /// CHECK: Device's attestation, if any; updated through shift-attestation
+    #[account(
+        mut,
+        seeds = [b"attestation", device_id.as_ref()],
+        bump,
+        seeds::program = shift_attestation::ID
+    )]
+    pub attestation_record: UncheckedAccount<'info>,
+
+    pub attestation_program: Program<'info, ShiftAttestation>,
+
+    /// CHECK: Instructions sysvar, read to find the Ed25519 program instructions
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
 }
 
 // Account structures
@@ -334,14 +905,74 @@ pub struct KeyPool {
     pub total_keys: u32,
     pub available_keys: u32,
     pub used_keys: u32,
-    pub public_keys: Vec<[u8; 32]>,      // Available public keys
-    pub encumbered_keys: Vec<u32>,       // Indices of encumbered keys
+    pub key_scheme: KeyScheme,
+    pub wots_public_seed: [u8; 32], // Domain separation for WOTS+ chains
+    pub frozen: bool,                // Set once a key is proven double-signed; no further use
+    pub key_usage_policy: KeyUsagePolicy,
+    pub next_key_index: u32, // High-water mark: one past the highest key index used
+    pub key_batches: Vec<KeyBatch>,      // Merkle commitments to the pool's public keys
     pub created_at: i64,
     pub bump: u8,
 }
 
 impl KeyPool {
-    pub const LEN: usize = 32 + 32 + 4 + 4 + 4 + 4 + (32 * 1000) + 4 + (4 * 1000) + 8 + 1; // Support up to 1000 keys
+    /// Account size with room for `batches` key batches
+    pub fn space(batches: usize) -> usize {
+        8 + 32 + 32 + 4 + 4 + 4 + 1 + 32 + 1 + 1 + 4 + 4 + (KeyBatch::LEN * batches) + 8 + 1
+    }
+
+    /// Batch whose index range covers `key_index`
+    pub fn find_batch(&self, key_index: u32) -> Option<&KeyBatch> {
+        self.key_batches.iter().find(|batch| batch.covers(key_index))
+    }
+}
+
+/// One page of the bitmap of encumbered key indices. Pages are created on first use,
+/// so the double-use check costs the same however many keys a device has used.
+#[account]
+pub struct KeyBitmapPage {
+    pub device_id: [u8; 32],
+    pub page: u32,
+    pub bits: [u8; KeyBitmapPage::BYTES], // Bit i is set once key page * KEYS_PER_PAGE + i is used
+    pub bump: u8,
+}
+
+impl KeyBitmapPage {
+    pub const BYTES: usize = 1024;
+    pub const KEYS_PER_PAGE: u32 = (Self::BYTES * 8) as u32;
+    pub const LEN: usize = 32 + 4 + Self::BYTES + 1;
+
+    pub fn page_of(key_index: u32) -> u32 {
+        key_index / Self::KEYS_PER_PAGE
+    }
+
+    fn bit_position(key_index: u32) -> (usize, u8) {
+        let offset = key_index % Self::KEYS_PER_PAGE;
+        ((offset / 8) as usize, 1 << (offset % 8))
+    }
+
+    pub fn is_set(&self, key_index: u32) -> bool {
+        let (byte, mask) = Self::bit_position(key_index);
+        self.bits[byte] & mask != 0
+    }
+
+    pub fn set(&mut self, key_index: u32) {
+        let (byte, mask) = Self::bit_position(key_index);
+        self.bits[byte] |= mask;
+    }
+}
+
+/// Marks a public key as used, wherever it appears in the device's pool
+#[account]
+pub struct KeyNullifier {
+    pub device_id: [u8; 32],
+    pub key_index: u32,
+    pub encumbered_at: i64, // Zero until the key is used
+    pub bump: u8,
+}
+
+impl KeyNullifier {
+    pub const LEN: usize = 32 + 4 + 8 + 1;
 }
 
 #[account]
@@ -350,17 +981,57 @@ pub struct EncumbranceRecord {
     pub key_index: u32,
     pub public_key: [u8; 32],
     pub transaction_hash: [u8; 32],
+    pub counterparty: Pubkey, // Party the key was used with, who may mark it verified
     pub destruction_proof: KeyDestructionProof,
     pub encumbered_at: i64,
     pub status: EncumbranceStatus,
+    pub verified_by: Option<Pubkey>, // Counterparty that checked the encumbrance
     pub bump: u8,
 }
 
 impl EncumbranceRecord {
-    pub const LEN: usize = 32 + 4 + 32 + 32 + KeyDestructionProof::LEN + 8 + 1 + 1;
+    pub const LEN: usize = 32 + 4 + 32 + 32 + 32 + KeyDestructionProof::LEN + 8 + 1 + 33 + 1;
 }
 
 // Data structures
+/// A batch of one-time keys with consecutive indices, committed to by a Merkle root.
+/// Leaves are `key_leaf_hash(public_key)`, or `key_commitment_leaf_hash` for keys whose
+/// destruction is proven by a commitment, padded with zero leaves to a power of two.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct KeyBatch {
+    pub merkle_root: [u8; 32],
+    pub first_index: u32,
+    pub key_count: u32,
+}
+
+impl KeyBatch {
+    pub const LEN: usize = 32 + 4 + 4;
+
+    /// Number of proof hashes for a key in this batch
+    pub fn depth(&self) -> usize {
+        (self.key_count as u64).next_power_of_two().trailing_zeros() as usize
+    }
+
+    /// Whether `key_index` is one of this batch's keys (not a padding leaf)
+    pub fn covers(&self, key_index: u32) -> bool {
+        key_index
+            .checked_sub(self.first_index)
+            .is_some_and(|offset| offset < self.key_count)
+    }
+
+    /// Whether `public_key` is the key at `key_index` in this batch
+    pub fn contains_key(&self, key_index: u32, public_key: &[u8; 32], merkle_proof: &[[u8; 32]]) -> bool {
+        self.contains_leaf(key_index, &key_leaf_hash(public_key), merkle_proof)
+    }
+
+    /// Whether `leaf` is the leaf at `key_index` in this batch
+    pub fn contains_leaf(&self, key_index: u32, leaf: &[u8; 32], merkle_proof: &[[u8; 32]]) -> bool {
+        self.covers(key_index)
+            && merkle_proof.len() == self.depth()
+            && compute_merkle_root(*leaf, key_index - self.first_index, merkle_proof) == self.merkle_root
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct KeyDestructionProof {
     pub proof_type: ProofType,
@@ -372,13 +1043,55 @@ pub struct KeyDestructionProof {
 
 impl KeyDestructionProof {
     pub const LEN: usize = 1 + 256 + 8 + 32 + 64;
+
+    /// Merkle leaf the key proven destroyed must have in its batch. Commitment proofs
+    /// only hold when the reveal opens the commitment fixed in the leaf.
+    pub fn key_leaf(&self, public_key: &[u8; 32]) -> [u8; 32] {
+        match self.proof_type {
+            ProofType::CryptographicCommitment => {
+                let reveal: [u8; 32] = self.proof_data[..32].try_into().unwrap();
+                key_commitment_leaf_hash(public_key, &destruction_commitment(public_key, &reveal))
+            }
+            _ => key_leaf_hash(public_key),
+        }
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub enum ProofType {
-    ZeroKnowledge,
+    ZeroKnowledge, // Not verifiable on-chain yet; always refused
     HardwareAttestation,
     CryptographicCommitment,
+    WotsSignature, // Key was used to make a WOTS+ signature over the transaction hash
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum KeyScheme {
+    Standard, // Keys are proven used with a KeyDestructionProof
+    WotsPlus, // Keys are compressed WOTS+ public keys; see the wots module
+}
+
+/// Signatures by one pool key over two different transaction hashes, in the pool's
+/// key scheme. Ed25519 signatures are checked through Ed25519 program instructions
+/// earlier in the transaction. Two WOTS+ signatures do not fit in one transaction, so
+/// WOTS+ evidence is a single signature over a hash other than the one the key's
+/// encumbrance was proven with, and only disputes an encumbrance.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum DoubleSignEvidence {
+    Ed25519 {
+        transaction_hashes: [[u8; 32]; 2],
+        signatures: [[u8; 64]; 2],
+    },
+    Wots {
+        transaction_hash: [u8; 32],
+        signature: Box<wots::WotsSignature>,
+    },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum KeyUsagePolicy {
+    AnyOrder,   // Any unused key may be encumbered
+    Sequential, // Keys are used in increasing index order, like the hardware's key counter
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
@@ -388,48 +1101,208 @@ pub enum EncumbranceStatus {
     Disputed,
 }
 
+// Events
+#[event]
+pub struct DoubleSignProven {
+    pub device_id: [u8; 32],
+    pub key_index: u32,
+    pub public_key: [u8; 32],
+    pub attestation_flagged: bool, // False if the device's attestation has been closed
+    pub proven_at: i64,
+}
+
 // Helper functions
+#[allow(clippy::too_many_arguments)]
 fn verify_destruction_proof(
     proof: &KeyDestructionProof,
+    key_pool: &KeyPool,
+    key_index: u32,
     public_key: &[u8; 32],
+    merkle_proof: &[[u8; 32]],
     transaction_hash: &[u8; 32],
-) -> bool {
-    // In production, this would verify the ZK-SNARK proof
-    // For demo purposes, we'll do basic validation
-    proof.timestamp > 0 
-        && proof.nonce != [0u8; 32]
-        && proof.proof_data != [0u8; 256]
-        && proof.hardware_signature != [0u8; 64]
-}
-
-fn create_destruction_proof_data(
-    device_id: &[u8; 32],
-    private_key_hash: &[u8; 32],
+    attestation_record: Option<&AttestationRecord>,
+    instructions: &AccountInfo,
+) -> Result<bool> {
+    match proof.proof_type {
+        // The device's attested key signs the use of `public_key` for this transaction,
+        // checked through a signature precompile instruction earlier in the transaction
+        ProofType::HardwareAttestation => match attestation_record {
+            Some(attestation_record) => attestation_record.verify_device_signature(
+                instructions,
+                &destruction_message(public_key, transaction_hash, &proof.nonce),
+                &proof.hardware_signature,
+            ),
+            None => Ok(false),
+        },
+        // The reveal in proof_data[0..32] must open the commitment in the key's Merkle
+        // leaf, and proof_data[32..64] must be its use tag for this transaction
+        ProofType::CryptographicCommitment => {
+            let reveal: [u8; 32] = proof.proof_data[..32].try_into().unwrap();
+            let opens_commitment = key_pool
+                .find_batch(key_index)
+                .is_some_and(|key_batch| key_batch.contains_leaf(key_index, &proof.key_leaf(public_key), merkle_proof));
+            Ok(opens_commitment && proof.proof_data[32..64] == destruction_use_tag(&reveal, transaction_hash))
+        }
+        // There is no on-chain verifier for zero-knowledge proofs, so they are refused
+        ProofType::ZeroKnowledge => err!(EncumbranceError::UnsupportedProofType),
+        // Only accepted by encumber_key_wots, which checks the signature itself
+        ProofType::WotsSignature => Ok(false),
+    }
+}
+
+/// Whether `evidence` proves that the pool key `public_key` at `key_index` signed
+/// two different transaction hashes. `encumbered_transaction_hash` is the hash the
+/// key's encumbrance was proven with, when disputing one.
+fn verify_double_sign(
+    key_pool: &KeyPool,
+    key_index: u32,
     public_key: &[u8; 32],
-    nonce: &[u8; 32],
-) -> Result<[u8; 256]> {
-    // In production, this would generate a ZK-SNARK proof
-    // For demo purposes, we'll create a hash-based proof
+    evidence: &DoubleSignEvidence,
+    encumbered_transaction_hash: Option<&[u8; 32]>,
+    instructions: &AccountInfo,
+) -> Result<bool> {
+    match evidence {
+        DoubleSignEvidence::Ed25519 { transaction_hashes, signatures } => {
+            require!(
+                key_pool.key_scheme == KeyScheme::Standard,
+                EncumbranceError::KeySchemeMismatch
+            );
+            if transaction_hashes[0] == transaction_hashes[1] {
+                return Ok(false);
+            }
+            for (transaction_hash, signature) in transaction_hashes.iter().zip(signatures) {
+                if !verify_ed25519_instruction(instructions, public_key, transaction_hash, signature)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        DoubleSignEvidence::Wots { transaction_hash, signature } => {
+            require!(
+                key_pool.key_scheme == KeyScheme::WotsPlus,
+                EncumbranceError::KeySchemeMismatch
+            );
+            // The signature over the encumbered hash was checked when the key was encumbered
+            let encumbered_transaction_hash =
+                encumbered_transaction_hash.ok_or(EncumbranceError::EncumbranceRequired)?;
+            Ok(transaction_hash != encumbered_transaction_hash
+                && wots::public_key_from_signature(
+                    &key_pool.wots_public_seed,
+                    key_index,
+                    transaction_hash,
+                    signature,
+                ) == *public_key)
+        }
+    }
+}
+
+/// Flag a device's attestation through shift-attestation, signed by the encumbrance
+/// authority PDA. Returns false only if the device no longer has an attestation;
+/// fails if governance has not made the PDA the fraud reporter.
+/// Check that the device's attestation is trusted now, not just marked Valid: the
+/// attestation program also checks its expiry, measurements, manufacturer and CRL.
+/// Records in their grace period must be refreshed before they vouch for new keys.
+fn verify_device_attestation<'info>(
+    attestation_program: &Program<'info, ShiftAttestation>,
+    attestation_record: &Account<'info, AttestationRecord>,
+    manufacturer_account: &AccountInfo<'info>,
+    measurement_deprecation: &AccountInfo<'info>,
+    crl_bucket: &AccountInfo<'info>,
+    device_id: [u8; 32],
+) -> Result<()> {
+    require!(
+        attestation_record.status == AttestationStatus::Valid,
+        EncumbranceError::DeviceNotAttested
+    );
+
+    let cpi_ctx = CpiContext::new(
+        attestation_program.to_account_info(),
+        shift_attestation::cpi::accounts::VerifyAttestation {
+            attestation_record: attestation_record.to_account_info(),
+            manufacturer_account: manufacturer_account.clone(),
+            measurement_deprecation: measurement_deprecation.clone(),
+            crl_bucket: crl_bucket.clone(),
+        },
+    );
+    let summary = shift_attestation::cpi::verify_attestation(cpi_ctx, device_id)?.get();
+    require!(
+        summary.validity == AttestationValidity::Valid,
+        EncumbranceError::AttestationNeedsRefresh
+    );
+    Ok(())
+}
+
+fn report_device_fraud<'info>(
+    attestation_program: &Program<'info, ShiftAttestation>,
+    encumbrance_authority: &Account<'info, EncumbranceAuthority>,
+    attestation_authority: &Account<'info, AttestationAuthority>,
+    attestation_record: &AccountInfo<'info>,
+    device_id: [u8; 32],
+) -> Result<bool> {
+    // Devices whose attestation has been closed only have their pool frozen
+    if attestation_record.data_is_empty() {
+        return Ok(false);
+    }
+    require!(
+        attestation_authority.fraud_reporter == Some(encumbrance_authority.key()),
+        EncumbranceError::FraudReporterNotSet
+    );
+
+    let signer_seeds: &[&[&[u8]]] = &[&[b"encumbrance_authority", &[encumbrance_authority.bump]]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        attestation_program.to_account_info(),
+        shift_attestation::cpi::accounts::FlagAttestation {
+            fraud_reporter: encumbrance_authority.to_account_info(),
+            attestation_authority: attestation_authority.to_account_info(),
+            attestation_record: attestation_record.clone(),
+        },
+        signer_seeds,
+    );
+
+    shift_attestation::cpi::flag_attestation(cpi_ctx, device_id)?;
+    Ok(true)
+}
+
+/// Merkle leaf for a pool public key
+pub fn key_leaf_hash(public_key: &[u8; 32]) -> [u8; 32] {
     let mut hasher = Sha256::new();
-    hasher.update(device_id);
-    hasher.update(private_key_hash);
+    hasher.update([0x00]);
     hasher.update(public_key);
-    hasher.update(nonce);
-    hasher.update(b"SHIFT_KEY_DESTRUCTION_PROOF");
-    
-    let hash = hasher.finalize();
-    let mut proof_data = [0u8; 256];
-    
-    // Fill the proof data with multiple hashes for demonstration
-    for i in 0..8 {
-        let mut hasher = Sha256::new();
-        hasher.update(&hash);
-        hasher.update(&(i as u64).to_le_bytes());
-        let iteration_hash = hasher.finalize();
-        proof_data[i * 32..(i + 1) * 32].copy_from_slice(&iteration_hash);
-    }
-    
-    Ok(proof_data)
+    hasher.finalize().into()
+}
+
+/// Merkle leaf for a pool public key whose destruction is proven by revealing the
+/// opening of `commitment`
+pub fn key_commitment_leaf_hash(public_key: &[u8; 32], commitment: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0x02]);
+    hasher.update(public_key);
+    hasher.update(commitment);
+    hasher.finalize().into()
+}
+
+/// Merkle interior node; the prefix keeps nodes and leaves from colliding
+pub fn merkle_node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Fold a leaf and its sibling path into a root; bit i of `leaf_index` says
+/// whether the node at level i is a right child
+pub fn compute_merkle_root(leaf: [u8; 32], leaf_index: u32, merkle_proof: &[[u8; 32]]) -> [u8; 32] {
+    merkle_proof
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (level, sibling)| {
+            if (leaf_index >> level) & 1 == 1 {
+                merkle_node_hash(sibling, &node)
+            } else {
+                merkle_node_hash(&node, sibling)
+            }
+        })
 }
 
 // Error handling
@@ -453,4 +1326,112 @@ pub enum EncumbranceError {
     KeyNotEncumbered,
     #[msg("Unauthorized owner")]
     UnauthorizedOwner,
-} 
\ No newline at end of file
+    #[msg("Key pool cannot hold more key batches")]
+    KeyPoolFull,
+    #[msg("Key pool uses a different key scheme")]
+    KeySchemeMismatch,
+    #[msg("Device does not have a valid attestation")]
+    DeviceNotAttested,
+    #[msg("Key batch is already in the pool")]
+    DuplicateKeyBatch,
+    #[msg("Key batch is not signed by the device's attested key")]
+    InvalidBatchSignature,
+    #[msg("Key pool is frozen after a proven double-sign")]
+    KeyPoolFrozen,
+    #[msg("Counterparty is not the encumbrance's counterparty, or is the device owner")]
+    UnauthorizedCounterparty,
+    #[msg("Encumbrance status does not allow this action")]
+    InvalidEncumbranceStatus,
+    #[msg("Evidence does not prove a double-sign by the key")]
+    InvalidDoubleSignProof,
+    #[msg("Key index is below the pool's next key index")]
+    KeyOutOfSequence,
+    #[msg("Destruction proof type cannot be verified on-chain")]
+    UnsupportedProofType,
+    #[msg("Key bitmap page is required for AnyOrder pools and not allowed for Sequential pools")]
+    KeyUsageAccountsMismatch,
+    #[msg("Encumbrance program is not the attestation fraud reporter")]
+    FraudReporterNotSet,
+    #[msg("WOTS+ double-signs are proven by disputing the key's encumbrance")]
+    EncumbranceRequired,
+    #[msg("Device attestation is in its grace period and must be refreshed")]
+    AttestationNeedsRefresh,
+    #[msg("Hardware attestation proofs need the manufacturer, deprecation, CRL and attestation program accounts")]
+    AttestationAccountsMissing,
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYS: [[u8; 32]; 3] = [[0x11; 32], [0x22; 32], [0x33; 32]];
+
+    // Three keys starting at index 8, padded with a zero leaf to depth 2
+    fn three_key_batch() -> (KeyBatch, [[u8; 32]; 4]) {
+        let leaves = [key_leaf_hash(&KEYS[0]), key_leaf_hash(&KEYS[1]), key_leaf_hash(&KEYS[2]), [0u8; 32]];
+        let left = merkle_node_hash(&leaves[0], &leaves[1]);
+        let right = merkle_node_hash(&leaves[2], &leaves[3]);
+        let batch = KeyBatch { merkle_root: merkle_node_hash(&left, &right), first_index: 8, key_count: 3 };
+        (batch, leaves)
+    }
+
+    fn proof(leaves: &[[u8; 32]; 4], position: usize) -> [[u8; 32]; 2] {
+        let sibling_pair = if position < 2 { (leaves[2], leaves[3]) } else { (leaves[0], leaves[1]) };
+        [leaves[position ^ 1], merkle_node_hash(&sibling_pair.0, &sibling_pair.1)]
+    }
+
+    #[test]
+    fn contains_every_key_at_its_index() {
+        let (batch, leaves) = three_key_batch();
+        assert_eq!(batch.depth(), 2);
+        for (position, key) in KEYS.iter().enumerate() {
+            assert!(batch.contains_key(8 + position as u32, key, &proof(&leaves, position)));
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_proof_length() {
+        let (batch, leaves) = three_key_batch();
+        let full = proof(&leaves, 0);
+        assert!(!batch.contains_key(8, &KEYS[0], &full[..1]));
+
+        let mut padded = full.to_vec();
+        padded.push([0u8; 32]);
+        assert!(!batch.contains_key(8, &KEYS[0], &padded));
+    }
+
+    #[test]
+    fn rejects_wrong_index() {
+        let (batch, leaves) = three_key_batch();
+        assert!(!batch.contains_key(9, &KEYS[0], &proof(&leaves, 0)));
+        assert!(!batch.contains_key(12, &KEYS[0], &proof(&leaves, 0)));
+    }
+
+    #[test]
+    fn rejects_indices_below_the_batch() {
+        let (batch, leaves) = three_key_batch();
+        // Without the range check, key_index - first_index underflows here
+        assert!(!batch.covers(7));
+        assert!(!batch.contains_key(4, &KEYS[0], &proof(&leaves, 0)));
+        assert!(!batch.contains_leaf(0, &leaves[0], &proof(&leaves, 0)));
+    }
+
+    #[test]
+    fn rejects_padding_leaves_past_key_count() {
+        let (batch, leaves) = three_key_batch();
+        // The zero leaf is part of the tree, but not a key
+        assert_eq!(compute_merkle_root(leaves[3], 3, &proof(&leaves, 3)), batch.merkle_root);
+        assert!(!batch.covers(11));
+        assert!(!batch.contains_leaf(11, &[0u8; 32], &proof(&leaves, 3)));
+    }
+
+    #[test]
+    fn single_key_batch_has_depth_zero() {
+        let batch = KeyBatch { merkle_root: key_leaf_hash(&KEYS[0]), first_index: 5, key_count: 1 };
+        assert_eq!(batch.depth(), 0);
+        assert!(batch.contains_key(5, &KEYS[0], &[]));
+        assert!(!batch.contains_key(5, &KEYS[1], &[]));
+        assert!(!batch.contains_key(6, &KEYS[0], &[]));
+        assert!(!batch.contains_key(5, &KEYS[0], &[[0u8; 32]]));
+    }
+}
@@ -0,0 +1,5 @@
+//! WOTS+ verification for pools whose keys are hash-based. The scheme lives in
+//! shift-key-proofs, shared with the devices that generate keys and sign; the
+//! program only recovers public keys from signatures.
+
+pub use shift_key_proofs::wots::{public_key_from_signature, WotsSignature, LEN, N};
@@ -1,6 +1,6 @@
 import { PublicKey } from "@solana/web3.js";
 import BN from "bn.js";
-import { AttestationRecord, AttestationStatus, HardwareType } from "./types";
+import { AttestationRecord, AttestationStatus, AttestationSummary, AttestationValidity, HardwareType, QuoteFormat, SecurityLevel } from "./types";
 
 export class ShiftAttestationClient {
   /**
@@ -9,6 +9,7 @@ export class ShiftAttestationClient {
   async createAttestation(
     deviceId: Uint8Array,
     manufacturerId: Uint8Array,
+    deviceOwner: PublicKey,
     certificate: Uint8Array
   ): Promise<string> {
     console.log("Creating hardware attestation...");
@@ -20,11 +21,20 @@ export class ShiftAttestationClient {
   /**
    * Verify hardware attestation
    */
-  async verifyAttestation(deviceId: Uint8Array): Promise<boolean> {
+  async verifyAttestation(deviceId: Uint8Array): Promise<AttestationSummary> {
     console.log("Verifying hardware attestation...");
     console.log("✅ Remote attestation verified");
     console.log("✅ Hardware is legitimate and untampered");
-    return true;
+    return {
+      deviceId,
+      deviceKey: new Uint8Array(32).fill(3),
+      manufacturerId: new Uint8Array(32).fill(1),
+      hardwareType: HardwareType.ShiftDevice,
+      securityLevel: SecurityLevel.SecureElement,
+      measurementSetId: new Uint8Array(32).fill(7),
+      expiresAt: new BN(Date.now() / 1000 + 30 * 24 * 60 * 60), // 30 days
+      validity: AttestationValidity.Valid,
+    };
   }
 
   /**
@@ -33,7 +43,11 @@ export class ShiftAttestationClient {
   async getAttestationRecord(deviceId: Uint8Array): Promise<AttestationRecord> {
     return {
       deviceId,
+      deviceOwner: new PublicKey("11111111111111111111111111111111"),
+      deviceKey: new Uint8Array(32).fill(3),
       manufacturerId: new Uint8Array(32).fill(1),
+      manufacturerKey: new Uint8Array(32).fill(8),
+      manufacturerGeneration: new BN(0),
       attestationQuote: {
         version: 1,
         signature: new Uint8Array(64).fill(2),
@@ -43,9 +57,18 @@ export class ShiftAttestationClient {
         measurements: [new Uint8Array(32).fill(5)]
       },
       deviceCertificate: new Uint8Array(1024).fill(6),
+      quoteFormat: QuoteFormat.Shift,
+      securityLevel: SecurityLevel.SecureElement,
+      measurementSetId: new Uint8Array(32).fill(7),
       status: AttestationStatus.Valid,
       createdAt: new BN(Date.now() / 1000),
+      attestedAt: new BN(Date.now() / 1000),
       expiresAt: new BN(Date.now() / 1000 + 30 * 24 * 60 * 60), // 30 days
+      historyLength: 0,
+      series: 0,
+      attestedBy: new PublicKey("11111111111111111111111111111111"),
+      endorsements: 1,
+      cosigned: false,
       bump: 255
     };
   }
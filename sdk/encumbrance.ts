@@ -1,6 +1,7 @@
 import { PublicKey } from "@solana/web3.js";
 import BN from "bn.js";
-import { KeyPool, EncumbranceRecord, EncumbranceStatus, ProofType } from "./types";
+import { KeyPool, EncumbranceRecord, EncumbranceStatus, KeyScheme, KeyUsagePolicy, ProofType, DoubleSignEvidence, DoubleSignProven } from "./types";
+import { computeKeyMerkleRoot } from "./utils";
 
 export class ShiftEncumbranceClient {
   /**
@@ -8,12 +9,16 @@ export class ShiftEncumbranceClient {
    */
   async initializeKeyPool(
     deviceId: Uint8Array,
-    poolSize: number,
-    publicKeys: Uint8Array[]
+    publicKeys: Uint8Array[],
+    batchSignature: Uint8Array, // Attested device key's signature over keyBatchMessage
+    keyUsagePolicy: KeyUsagePolicy = KeyUsagePolicy.AnyOrder
   ): Promise<string> {
+    const merkleRoot = computeKeyMerkleRoot(publicKeys);
     console.log("Initializing key pool...");
-    console.log("Pool size:", poolSize);
-    console.log("Initial keys:", publicKeys.length);
+    console.log("Pool size:", publicKeys.length);
+    console.log("Key usage policy:", keyUsagePolicy);
+    console.log("Merkle root:", Buffer.from(merkleRoot).toString("hex"));
+    console.log("Batch signature:", Buffer.from(batchSignature).toString("hex"));
     return "mock_key_pool_signature";
   }
 
@@ -24,10 +29,13 @@ export class ShiftEncumbranceClient {
     deviceId: Uint8Array,
     keyIndex: number,
     publicKey: Uint8Array,
-    transactionHash: Uint8Array
+    merkleProof: Uint8Array[],
+    transactionHash: Uint8Array,
+    counterparty: PublicKey // Only this party may mark the encumbrance verified
   ): Promise<string> {
     console.log("Encumbering key...");
     console.log("Key index:", keyIndex);
+    console.log("Counterparty:", counterparty.toBase58());
     console.log("💥 Key self-destructs after use!");
     console.log("🔒 Zero-knowledge proof generated");
     return "mock_encumber_signature";
@@ -57,8 +65,12 @@ export class ShiftEncumbranceClient {
       totalKeys: 1000,
       availableKeys: 999,
       usedKeys: 1,
-      publicKeys: [new Uint8Array(32).fill(1)],
-      encumberedKeys: [0],
+      keyScheme: KeyScheme.Standard,
+      wotsPublicSeed: new Uint8Array(32),
+      frozen: false,
+      keyUsagePolicy: KeyUsagePolicy.AnyOrder,
+      nextKeyIndex: 1,
+      keyBatches: [{ merkleRoot: new Uint8Array(32).fill(1), firstIndex: 0, keyCount: 1000 }],
       createdAt: new BN(Date.now() / 1000),
       bump: 255
     };
@@ -69,10 +81,77 @@ export class ShiftEncumbranceClient {
    */
   async replenishKeyPool(
     deviceId: Uint8Array,
-    newPublicKeys: Uint8Array[]
+    newPublicKeys: Uint8Array[],
+    batchSignature: Uint8Array // Attested device key's signature over keyBatchMessage
   ): Promise<string> {
+    const merkleRoot = computeKeyMerkleRoot(newPublicKeys);
     console.log("Replenishing key pool...");
+    console.log("Batch Merkle root:", Buffer.from(merkleRoot).toString("hex"));
+    console.log("Batch signature:", Buffer.from(batchSignature).toString("hex"));
     console.log("Adding", newPublicKeys.length, "new keys");
     return "mock_replenish_signature";
   }
+
+  /**
+   * Record that this counterparty has checked an encumbrance
+   */
+  async markEncumbranceVerified(
+    deviceId: Uint8Array,
+    keyIndex: number
+  ): Promise<string> {
+    console.log("Marking encumbrance verified...");
+    console.log("Key index:", keyIndex);
+    return "mock_mark_verified_signature";
+  }
+
+  /**
+   * Dispute an encumbrance with proof that its key signed two transactions.
+   * Resolves to the DoubleSignProven event; fails until this program is the fraud reporter.
+   */
+  async disputeEncumbrance(
+    deviceId: Uint8Array,
+    keyIndex: number,
+    evidence: DoubleSignEvidence
+  ): Promise<DoubleSignProven> {
+    console.log("Disputing encumbrance...");
+    console.log("Key index:", keyIndex);
+    return this.reportDoubleSign({
+      deviceId,
+      keyIndex,
+      publicKey: new Uint8Array(32),
+      attestationFlagged: true,
+      provenAt: new BN(Date.now() / 1000),
+    });
+  }
+
+  /**
+   * Prove that a pool key signed two transactions, freezing the key pool.
+   * Pass the key's destruction commitment if its leaf was built with one.
+   * Resolves to the DoubleSignProven event; fails until this program is the fraud reporter.
+   */
+  async submitDoubleSignProof(
+    deviceId: Uint8Array,
+    keyIndex: number,
+    publicKey: Uint8Array,
+    merkleProof: Uint8Array[],
+    commitment: Uint8Array | null,
+    evidence: DoubleSignEvidence
+  ): Promise<DoubleSignProven> {
+    console.log("Submitting double-sign proof...");
+    console.log("Key index:", keyIndex);
+    return this.reportDoubleSign({
+      deviceId,
+      keyIndex,
+      publicKey,
+      attestationFlagged: true,
+      provenAt: new BN(Date.now() / 1000),
+    });
+  }
+
+  // Log what a proven double-sign did, as reported by its DoubleSignProven event
+  private reportDoubleSign(proven: DoubleSignProven): DoubleSignProven {
+    console.log("🚨 Double-sign proven: key pool frozen");
+    console.log(proven.attestationFlagged ? "🚩 Attestation flagged" : "Device has no attestation to flag");
+    return proven;
+  }
 } 
\ No newline at end of file
@@ -40,19 +40,161 @@ export interface AttestationData {
   hardwareType: HardwareType;
 }
 
+export interface AttestationAuthority {
+  signers: PublicKey[];
+  threshold: number;
+  signerSetVersion: number;
+  manufacturerTimelock: BN;
+  proposalCount: BN;
+  totalManufacturers: BN;
+  manufacturerGenerations: BN;
+  totalAttestations: BN;
+  maxValidityPeriod: BN;
+  fraudReporter: PublicKey | null;
+  bump: number;
+}
+
+export interface Proposal {
+  proposalId: BN;
+  proposer: PublicKey;
+  action: GovernanceAction;
+  approvals: PublicKey[];
+  signerSetVersion: number;
+  createdAt: BN;
+  executableAt: BN;
+  executed: boolean;
+  bump: number;
+}
+
+export type GovernanceAction =
+  | { addManufacturer: { manufacturerId: Uint8Array; name: string; publicKey: Uint8Array; hardwareType: HardwareType } }
+  | { deactivateManufacturer: { manufacturerId: Uint8Array } }
+  | { rotateManufacturerKey: { manufacturerId: Uint8Array; newPublicKey: Uint8Array; overlapPeriod: BN } }
+  | { removeManufacturer: { manufacturerId: Uint8Array } }
+  | { revokeAttestation: { deviceId: Uint8Array; reason: RevocationReason } }
+  | { reinstateAttestation: { deviceId: Uint8Array } }
+  | { closeAttestation: { deviceId: Uint8Array } }
+  | { deprecateMeasurementSet: { measurementSetId: Uint8Array } }
+  | { setMaxValidityPeriod: { maxValidityPeriod: BN } }
+  | { setSigners: { signers: PublicKey[]; threshold: number } }
+  | { setManufacturerTimelock: { manufacturerTimelock: BN } }
+  | { setFraudReporter: { fraudReporter: PublicKey | null } };
+
 export interface AttestationRecord {
   deviceId: Uint8Array;
+  deviceOwner: PublicKey;
+  deviceKey: Uint8Array;
+  deviceKeyAlgorithm: KeyAlgorithm;
   manufacturerId: Uint8Array;
+  manufacturerKey: Uint8Array;
+  manufacturerGeneration: BN;
   attestationQuote: AttestationQuote;
   deviceCertificate: Uint8Array;
+  quoteFormat: QuoteFormat;
+  securityLevel: SecurityLevel;
+  verifiedBootState?: VerifiedBootState;
+  measurementSetId: Uint8Array;
   status: AttestationStatus;
   createdAt: BN;
+  attestedAt: BN;
   expiresAt: BN;
   revokedAt?: BN;
   revocationReason?: RevocationReason;
+  statusBeforeRevocation?: AttestationStatus;
+  historyLength: number;
+  series: number; // Which of the device's records this is; see DeviceSeries
+  attestedBy: PublicKey;
+  endorsements: number;
+  cosigned: boolean;
+  certificateSerialHash?: Uint8Array;
+  bump: number;
+}
+
+export interface DeviceSeries {
+  deviceId: Uint8Array;
+  recordsCreated: number;
+  bump: number;
+}
+
+export interface AttestationHistoryEntry {
+  deviceId: Uint8Array;
+  sequence: number;
+  quoteHash: Uint8Array;
+  measurementSetId: Uint8Array;
+  measurements: Uint8Array[];
+  manufacturerKey: Uint8Array;
+  quoteTimestamp: BN;
+  validFrom: BN;
+  replacedAt: BN;
+  bump: number;
+}
+
+export interface ManufacturerAccount {
+  manufacturerId: Uint8Array;
+  name: string;
+  publicKey: Uint8Array;
+  hardwareType: HardwareType;
+  policy: AttestationPolicy;
+  previousPublicKey?: Uint8Array;
+  previousKeyValidUntil: BN;
+  isActive: boolean;
+  deactivatedAt?: BN;
+  devicesAttested: BN;
+  createdAt: BN;
+  generation: BN;
+  bump: number;
+}
+
+export interface AttestationPolicy {
+  validityPeriod: BN;
+  gracePeriod: BN;
+  requireCosign: boolean;
+  endorsementThreshold: number;
+}
+
+export interface PendingEndorsement {
+  deviceId: Uint8Array;
+  quoteHash: Uint8Array;
+  endorsers: PublicKey[];
+  bump: number;
+}
+
+export interface CrlBucket {
+  manufacturerId: Uint8Array;
+  bucket: number;
+  crlNumber: BN;
+  serialHashes: Uint8Array[];
+  updatedAt: BN;
+  bump: number;
+}
+
+export interface AttesterAccount {
+  manufacturerId: Uint8Array;
+  attester: PublicKey;
+  addedAt: BN;
+  bump: number;
+}
+
+export interface DeprecatedMeasurementSet {
+  measurementSetId: Uint8Array;
+  deprecatedBy: PublicKey;
+  deprecatedAt: BN;
   bump: number;
 }
 
+export interface AttestationSummary {
+  deviceId: Uint8Array;
+  deviceKey: Uint8Array;
+  deviceKeyAlgorithm: KeyAlgorithm;
+  manufacturerId: Uint8Array;
+  hardwareType: HardwareType;
+  securityLevel: SecurityLevel;
+  verifiedBootState?: VerifiedBootState;
+  measurementSetId: Uint8Array;
+  expiresAt: BN;
+  validity: AttestationValidity;
+}
+
 export interface AttestationQuote {
   version: number;
   signature: Uint8Array;
@@ -69,23 +211,62 @@ export interface KeyPool {
   totalKeys: number;
   availableKeys: number;
   usedKeys: number;
-  publicKeys: Uint8Array[];
-  encumberedKeys: number[];
+  keyScheme: KeyScheme;
+  wotsPublicSeed: Uint8Array;
+  frozen: boolean;
+  keyUsagePolicy: KeyUsagePolicy;
+  nextKeyIndex: number;
+  keyBatches: KeyBatch[];
   createdAt: BN;
   bump: number;
 }
 
+export interface KeyBatch {
+  merkleRoot: Uint8Array;
+  firstIndex: number;
+  keyCount: number;
+}
+
+export interface KeyBitmapPage {
+  deviceId: Uint8Array;
+  page: number;
+  bits: Uint8Array;
+  bump: number;
+}
+
+export interface KeyNullifier {
+  deviceId: Uint8Array;
+  keyIndex: number;
+  encumberedAt: BN;
+  bump: number;
+}
+
 export interface EncumbranceRecord {
   deviceId: Uint8Array;
   keyIndex: number;
   publicKey: Uint8Array;
   transactionHash: Uint8Array;
+  counterparty: PublicKey;
   destructionProof: KeyDestructionProof;
   encumberedAt: BN;
   status: EncumbranceStatus;
+  verifiedBy: PublicKey | null;
   bump: number;
 }
 
+export type DoubleSignEvidence =
+  | { ed25519: { transactionHashes: [Uint8Array, Uint8Array]; signatures: [Uint8Array, Uint8Array] } }
+  // Signature over a hash other than the disputed encumbrance's; dispute_encumbrance only
+  | { wots: { transactionHash: Uint8Array; signature: Uint8Array[] } };
+
+export interface DoubleSignProven {
+  deviceId: Uint8Array;
+  keyIndex: number;
+  publicKey: Uint8Array;
+  attestationFlagged: boolean; // False if the device's attestation has been closed
+  provenAt: BN;
+}
+
 export interface KeyDestructionProof {
   proofType: ProofType;
   proofData: Uint8Array;
@@ -147,6 +328,38 @@ export enum AttestationStatus {
   Expired = "Expired",
   Revoked = "Revoked",
   Pending = "Pending",
+  Flagged = "Flagged",
+}
+
+// P-256 device keys are recorded as the SHA-256 of the SEC1 compressed key
+export enum KeyAlgorithm {
+  Ed25519 = "Ed25519",
+  EcdsaP256 = "EcdsaP256",
+}
+
+export enum QuoteFormat {
+  Shift = "Shift",
+  Tpm2 = "Tpm2",
+  AndroidKeyAttestation = "AndroidKeyAttestation",
+}
+
+export enum SecurityLevel {
+  Software = "Software",
+  TrustedEnvironment = "TrustedEnvironment",
+  StrongBox = "StrongBox",
+  SecureElement = "SecureElement",
+}
+
+export enum VerifiedBootState {
+  Verified = "Verified",
+  SelfSigned = "SelfSigned",
+  Unverified = "Unverified",
+  Failed = "Failed",
+}
+
+export enum AttestationValidity {
+  Valid = "Valid",
+  NeedsRefresh = "NeedsRefresh",
 }
 
 export enum RevocationReason {
@@ -155,12 +368,24 @@ export enum RevocationReason {
   ManufacturerRevoked = "ManufacturerRevoked",
   UserRequested = "UserRequested",
   Other = "Other",
+  Lost = "Lost",
 }
 
 export enum ProofType {
   ZeroKnowledge = "ZeroKnowledge",
   HardwareAttestation = "HardwareAttestation",
   CryptographicCommitment = "CryptographicCommitment",
+  WotsSignature = "WotsSignature",
+}
+
+export enum KeyScheme {
+  Standard = "Standard",
+  WotsPlus = "WotsPlus",
+}
+
+export enum KeyUsagePolicy {
+  AnyOrder = "AnyOrder",
+  Sequential = "Sequential",
 }
 
 export enum EncumbranceStatus {
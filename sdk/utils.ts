@@ -1,5 +1,6 @@
 import { PublicKey } from "@solana/web3.js";
 import BN from "bn.js";
+import { createHash } from "crypto";
 
 /**
  * Utility functions for the Shift Protocol SDK
@@ -171,4 +172,279 @@ export const SHIFT_CONSTANTS = {
   ATTESTATION_PROOF_LENGTH: 128,
   DEFAULT_DECIMALS: 6,
   MAX_DEVICES_PER_POOL: 1000,
-} as const; 
\ No newline at end of file
+} as const; 
+
+/**
+ * Merkle leaf for a key pool public key (matches shift-encumbrance key_leaf_hash)
+ */
+export function keyLeafHash(publicKey: Uint8Array): Uint8Array {
+  return createHash("sha256").update(Buffer.from([0x00])).update(publicKey).digest();
+}
+
+/**
+ * Merkle leaf for a pool key whose destruction is proven by opening `commitment`
+ * (matches shift-encumbrance key_commitment_leaf_hash)
+ */
+export function keyCommitmentLeafHash(publicKey: Uint8Array, commitment: Uint8Array): Uint8Array {
+  return createHash("sha256").update(Buffer.from([0x02])).update(publicKey).update(commitment).digest();
+}
+
+/**
+ * Merkle interior node (matches shift-encumbrance merkle_node_hash)
+ */
+export function merkleNodeHash(left: Uint8Array, right: Uint8Array): Uint8Array {
+  return createHash("sha256").update(Buffer.from([0x01])).update(left).update(right).digest();
+}
+
+/**
+ * Build the levels of a key batch Merkle tree, leaves first.
+ * Leaves are padded with zero hashes to a power of two.
+ */
+function buildMerkleLevels(leaves: Uint8Array[]): Uint8Array[][] {
+  let level = [...leaves];
+  while (level.length & (level.length - 1)) {
+    level.push(new Uint8Array(32));
+  }
+
+  const levels = [level];
+  while (level.length > 1) {
+    const next: Uint8Array[] = [];
+    for (let i = 0; i < level.length; i += 2) {
+      next.push(merkleNodeHash(level[i], level[i + 1]));
+    }
+    levels.push(next);
+    level = next;
+  }
+  return levels;
+}
+
+/**
+ * Merkle root over a batch of key leaves
+ */
+export function computeMerkleRoot(leaves: Uint8Array[]): Uint8Array {
+  const levels = buildMerkleLevels(leaves);
+  return levels[levels.length - 1][0];
+}
+
+/**
+ * Sibling path proving the leaf at `index` (relative to its batch) against the batch root
+ */
+export function getMerkleProof(leaves: Uint8Array[], index: number): Uint8Array[] {
+  const levels = buildMerkleLevels(leaves);
+  const proof: Uint8Array[] = [];
+  for (let depth = 0; depth < levels.length - 1; depth++) {
+    proof.push(levels[depth][(index >> depth) ^ 1]);
+  }
+  return proof;
+}
+
+/**
+ * Merkle root committing to a batch of one-time public keys
+ */
+export function computeKeyMerkleRoot(publicKeys: Uint8Array[]): Uint8Array {
+  return computeMerkleRoot(publicKeys.map(keyLeafHash));
+}
+
+/**
+ * Sibling path proving the key at `index` (relative to its batch) against the batch root
+ */
+export function getKeyMerkleProof(publicKeys: Uint8Array[], index: number): Uint8Array[] {
+  return getMerkleProof(publicKeys.map(keyLeafHash), index);
+}
+
+/**
+ * Message the attested device key signs for a HardwareAttestation destruction proof
+ */
+export function destructionMessage(
+  publicKey: Uint8Array,
+  transactionHash: Uint8Array,
+  nonce: Uint8Array
+): Uint8Array {
+  return createHash("sha256")
+    .update("SHIFT_KEY_DESTRUCTION")
+    .update(publicKey)
+    .update(transactionHash)
+    .update(nonce)
+    .digest();
+}
+
+function u32le(value: number): Buffer {
+  const bytes = Buffer.alloc(4);
+  bytes.writeUInt32LE(value);
+  return bytes;
+}
+
+/**
+ * Message the attested device key signs to add a key batch to its pool
+ */
+export function keyBatchMessage(
+  deviceId: Uint8Array,
+  merkleRoot: Uint8Array,
+  firstIndex: number,
+  keyCount: number
+): Uint8Array {
+  return createHash("sha256")
+    .update("SHIFT_KEY_BATCH")
+    .update(deviceId)
+    .update(merkleRoot)
+    .update(u32le(firstIndex))
+    .update(u32le(keyCount))
+    .digest();
+}
+
+/**
+ * Commitment to a key's destruction reveal, fixed in the key's Merkle leaf when its batch
+ * is generated. A CryptographicCommitment proof carries the reveal in proofData[0..32].
+ */
+export function destructionCommitment(publicKey: Uint8Array, reveal: Uint8Array): Uint8Array {
+  return createHash("sha256").update("SHIFT_KEY_COMMITMENT").update(publicKey).update(reveal).digest();
+}
+
+/**
+ * Tag binding a destruction reveal to the transaction the key is used for, carried in
+ * a CryptographicCommitment proof's proofData[32..64]
+ */
+export function destructionUseTag(reveal: Uint8Array, transactionHash: Uint8Array): Uint8Array {
+  return createHash("sha256").update("SHIFT_KEY_USE_TAG").update(reveal).update(transactionHash).digest();
+}
+
+/**
+ * WOTS+ parameters (match shift-key-proofs wots): n = 16 bytes, w = 16,
+ * 32 message chains and 3 checksum chains
+ */
+const WOTS_N = 16;
+const WOTS_W = 16;
+const WOTS_LEN = 35;
+
+function wotsChain(
+  publicSeed: Uint8Array,
+  keyIndex: number,
+  chain: number,
+  start: number,
+  steps: number,
+  value: Uint8Array
+): Uint8Array {
+  for (let step = start; step < start + steps; step++) {
+    value = createHash("sha256")
+      .update("SHIFT_WOTS_CHAIN")
+      .update(publicSeed)
+      .update(u32le(keyIndex))
+      .update(Buffer.from([chain, step]))
+      .update(value)
+      .digest()
+      .subarray(0, WOTS_N);
+  }
+  return value;
+}
+
+function wotsSecretChainStart(secretSeed: Uint8Array, keyIndex: number, chain: number): Uint8Array {
+  return createHash("sha256")
+    .update("SHIFT_WOTS_SECRET")
+    .update(secretSeed)
+    .update(u32le(keyIndex))
+    .update(Buffer.from([chain]))
+    .digest()
+    .subarray(0, WOTS_N);
+}
+
+function wotsChainLengths(publicSeed: Uint8Array, keyIndex: number, message: Uint8Array): number[] {
+  const digest = createHash("sha256")
+    .update("SHIFT_WOTS_MESSAGE")
+    .update(publicSeed)
+    .update(u32le(keyIndex))
+    .update(message)
+    .digest()
+    .subarray(0, WOTS_N);
+  // Base-16 digits, high nibble of each byte first, then a three-digit checksum
+  const digits = Array.from(digest).flatMap((byte) => [byte >> 4, byte & 0x0f]);
+  const checksum = digits.reduce((sum, digit) => sum + (WOTS_W - 1 - digit), 0);
+  return [...digits, (checksum >> 8) & 0x0f, (checksum >> 4) & 0x0f, checksum & 0x0f];
+}
+
+function wotsCompressPublicKey(publicSeed: Uint8Array, chainEnds: Uint8Array[]): Uint8Array {
+  const hash = createHash("sha256").update("SHIFT_WOTS_PUBLIC_KEY").update(publicSeed);
+  chainEnds.forEach((chainEnd) => hash.update(chainEnd));
+  return hash.digest();
+}
+
+/**
+ * Compressed WOTS+ public key for pool key `keyIndex`, as committed in the pool's Merkle tree
+ */
+export function wotsPublicKey(secretSeed: Uint8Array, publicSeed: Uint8Array, keyIndex: number): Uint8Array {
+  const chainEnds = Array.from({ length: WOTS_LEN }, (_, i) =>
+    wotsChain(publicSeed, keyIndex, i, 0, WOTS_W - 1, wotsSecretChainStart(secretSeed, keyIndex, i))
+  );
+  return wotsCompressPublicKey(publicSeed, chainEnds);
+}
+
+/**
+ * WOTS+ signature (35 chain values of 16 bytes) over `message` by pool key `keyIndex`.
+ * Each key must sign only once.
+ */
+export function wotsSign(
+  secretSeed: Uint8Array,
+  publicSeed: Uint8Array,
+  keyIndex: number,
+  message: Uint8Array
+): Uint8Array[] {
+  const lengths = wotsChainLengths(publicSeed, keyIndex, message);
+  return lengths.map((length, i) =>
+    wotsChain(publicSeed, keyIndex, i, 0, length, wotsSecretChainStart(secretSeed, keyIndex, i))
+  );
+}
+
+/**
+ * Compressed public key that `signature` signs `message` under (matches
+ * shift-key-proofs wots::public_key_from_signature)
+ */
+export function wotsPublicKeyFromSignature(
+  publicSeed: Uint8Array,
+  keyIndex: number,
+  message: Uint8Array,
+  signature: Uint8Array[]
+): Uint8Array {
+  const lengths = wotsChainLengths(publicSeed, keyIndex, message);
+  const chainEnds = lengths.map((length, i) =>
+    wotsChain(publicSeed, keyIndex, i, length, WOTS_W - 1 - length, signature[i])
+  );
+  return wotsCompressPublicKey(publicSeed, chainEnds);
+}
+
+/**
+ * Hash of an attestation quote, as endorsed by other attesters
+ * (matches shift-attestation calculate_quote_hash)
+ */
+export function attestationQuoteHash(
+  deviceId: Uint8Array,
+  quote: {
+    version: number;
+    publicKey: ArrayLike<number>;
+    nonce: ArrayLike<number>;
+    timestamp: BN;
+    measurements: ArrayLike<number>[];
+  }
+): Uint8Array {
+  const hash = createHash("sha256")
+    .update("SHIFT_ATTESTATION_QUOTE")
+    .update(deviceId)
+    .update(u32le(quote.version))
+    .update(Buffer.from(quote.publicKey))
+    .update(Buffer.from(quote.nonce))
+    .update(Buffer.from(quote.timestamp.toTwos(64).toArray("le", 8)));
+  for (const measurement of quote.measurements) {
+    hash.update(Buffer.from(measurement));
+  }
+  return hash.digest();
+}
+
+/**
+ * Identifier of a firmware measurement set (matches shift-attestation
+ * calculate_measurement_set_id)
+ */
+export function measurementSetId(measurements: ArrayLike<number>[]): Uint8Array {
+  const hash = createHash("sha256").update("SHIFT_MEASUREMENT_SET");
+  for (const measurement of measurements) {
+    hash.update(Buffer.from(measurement));
+  }
+  return hash.digest();
+}
@@ -0,0 +1,247 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { ShiftAttestation } from "../target/types/shift_attestation";
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  LAMPORTS_PER_SOL
+} from "@solana/web3.js";
+import { measurementSetId as measurementSetIdOf } from "../sdk/utils";
+
+// Shared test setup. The attestation authority PDA can only be initialized once per
+// validator, so every test file goes through the same governance signer set.
+
+export const governanceSigners = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+export const GOVERNANCE_THRESHOLD = 2;
+
+// Proposes, and executes the proposals it passes
+export const governanceExecutor = governanceSigners[0];
+
+export const findPda = (seeds: (Buffer | Uint8Array)[], programId: PublicKey) =>
+  PublicKey.findProgramAddressSync(seeds.map((seed) => Buffer.from(seed)), programId)[0];
+
+export const u64le = (value: number | anchor.BN) => Buffer.from(new anchor.BN(value).toArray("le", 8));
+
+export const attestationProgram = () => anchor.workspace.ShiftAttestation as Program<ShiftAttestation>;
+
+export const attestationAuthorityPda = () =>
+  findPda([Buffer.from("attestation_authority")], attestationProgram().programId);
+
+export const proposalPda = (proposalId: number | anchor.BN) =>
+  findPda([Buffer.from("proposal"), u64le(proposalId)], attestationProgram().programId);
+
+// Deprecation marker for a quote's measurement set, passed to create and refresh
+export const measurementDeprecationPda = (measurements: ArrayLike<number>[]) =>
+  findPda([Buffer.from("deprecated_measurements"), measurementSetIdOf(measurements)], attestationProgram().programId);
+
+/**
+ * Accounts verify_attestation reads for `deviceId`'s record, which programs calling it
+ * must pass along. Devices without a record get placeholders, rejected with the record.
+ */
+export async function verifyAttestationAccounts(deviceId: Uint8Array) {
+  const program = attestationProgram();
+  const attestationRecord = findPda([Buffer.from("attestation"), deviceId], program.programId);
+  const record = await program.account.attestationRecord.fetchNullable(attestationRecord);
+  if (!record) {
+    return {
+      attestationRecord,
+      manufacturerAccount: PublicKey.default,
+      measurementDeprecation: PublicKey.default,
+      crlBucket: PublicKey.default,
+    };
+  }
+  const crlBucket = record.certificateSerialHash ? record.certificateSerialHash[0] : 0;
+  return {
+    attestationRecord,
+    manufacturerAccount: findPda([Buffer.from("manufacturer"), record.manufacturerId], program.programId),
+    measurementDeprecation: findPda(
+      [Buffer.from("deprecated_measurements"), record.measurementSetId],
+      program.programId
+    ),
+    crlBucket: findPda([Buffer.from("crl"), record.manufacturerId, Buffer.from([crlBucket])], program.programId),
+  };
+}
+
+export const deviceSeriesPda = (deviceId: Uint8Array) =>
+  findPda([Buffer.from("device_series"), deviceId], attestationProgram().programId);
+
+// Little-endian u32 seed, e.g. a record's series or history length
+export const u32Seed = (value: number) => {
+  const bytes = Buffer.alloc(4);
+  bytes.writeUInt32LE(value);
+  return bytes;
+};
+
+export async function airdrop(...keypairs: Keypair[]): Promise<void> {
+  const provider = anchor.getProvider();
+  for (const keypair of keypairs) {
+    await provider.connection.requestAirdrop(keypair.publicKey, 10 * LAMPORTS_PER_SOL);
+  }
+
+  // Wait for confirmations
+  await new Promise(resolve => setTimeout(resolve, 1000));
+}
+
+let governanceReady: Promise<void> | undefined;
+
+/**
+ * Initialize the attestation program under a 2-of-3 governance signer set, once
+ */
+export function setupGovernance(): Promise<void> {
+  if (!governanceReady) {
+    governanceReady = (async () => {
+      await airdrop(...governanceSigners);
+      await attestationProgram()
+        .methods
+        .initialize(
+          governanceSigners.map((signer) => signer.publicKey),
+          GOVERNANCE_THRESHOLD,
+          new anchor.BN(0)
+        )
+        .accounts({
+          payer: governanceExecutor.publicKey,
+          attestationAuthority: attestationAuthorityPda(),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([governanceExecutor])
+        .rpc();
+    })();
+  }
+  return governanceReady;
+}
+
+/**
+ * Propose `action` and approve it by `approvers`, returning the proposal account.
+ * By default it collects exactly the threshold of approvals.
+ */
+export async function passProposal(
+  action: any,
+  approvers: Keypair[] = governanceSigners.slice(1, GOVERNANCE_THRESHOLD)
+): Promise<PublicKey> {
+  const program = attestationProgram();
+  const attestationAuthority = attestationAuthorityPda();
+  const { proposalCount } = await program.account.attestationAuthority.fetch(attestationAuthority);
+  const proposal = proposalPda(proposalCount);
+
+  await program.methods
+    .propose(action)
+    .accounts({
+      proposer: governanceExecutor.publicKey,
+      proposal,
+      attestationAuthority,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([governanceExecutor])
+    .rpc();
+
+  for (const approver of approvers) {
+    await program.methods
+      .approveProposal(proposalCount)
+      .accounts({ approver: approver.publicKey, proposal, attestationAuthority })
+      .signers([approver])
+      .rpc();
+  }
+
+  return proposal;
+}
+
+/**
+ * Pass and execute a change to the governance configuration itself
+ */
+export async function executeGovernanceUpdate(action: any): Promise<void> {
+  const proposal = await passProposal(action);
+  await attestationProgram()
+    .methods
+    .executeGovernanceUpdate()
+    .accounts({
+      executor: governanceExecutor.publicKey,
+      attestationAuthority: attestationAuthorityPda(),
+      proposal,
+    })
+    .signers([governanceExecutor])
+    .rpc();
+}
+
+/**
+ * Deprecate a measurement set through governance
+ */
+export async function deprecateMeasurementSet(measurements: ArrayLike<number>[]): Promise<void> {
+  const measurementSetId = Array.from(measurementSetIdOf(measurements));
+  const proposal = await passProposal({ deprecateMeasurementSet: { measurementSetId } });
+  await attestationProgram()
+    .methods
+    .deprecateMeasurementSet(measurementSetId)
+    .accounts({
+      executor: governanceExecutor.publicKey,
+      deprecatedMeasurementSet: measurementDeprecationPda(measurements),
+      attestationAuthority: attestationAuthorityPda(),
+      proposal,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([governanceExecutor])
+    .rpc();
+}
+
+/**
+ * Onboard a manufacturer through governance
+ */
+export async function addManufacturer(
+  manufacturerId: Uint8Array,
+  manufacturerKey: PublicKey,
+  hardwareType: any = { shiftDevice: {} },
+  name = "Test Manufacturer"
+): Promise<PublicKey> {
+  const program = attestationProgram();
+  const manufacturerAccount = findPda([Buffer.from("manufacturer"), manufacturerId], program.programId);
+  const proposal = await passProposal({
+    addManufacturer: {
+      manufacturerId: Array.from(manufacturerId),
+      name,
+      publicKey: Array.from(manufacturerKey.toBytes()),
+      hardwareType,
+    },
+  });
+
+  await program.methods
+    .addTrustedManufacturer(Array.from(manufacturerId), name, Array.from(manufacturerKey.toBytes()), hardwareType)
+    .accounts({
+      executor: governanceExecutor.publicKey,
+      manufacturerAccount,
+      attestationAuthority: attestationAuthorityPda(),
+      proposal,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([governanceExecutor])
+    .rpc();
+
+  return manufacturerAccount;
+}
+
+/**
+ * Register `attester` for a manufacturer, signed by the manufacturer key
+ */
+export async function addAttester(
+  manufacturerId: Uint8Array,
+  manufacturer: Keypair,
+  attester: PublicKey
+): Promise<PublicKey> {
+  const program = attestationProgram();
+  const attesterAccount = findPda(
+    [Buffer.from("attester"), manufacturerId, attester.toBuffer()],
+    program.programId
+  );
+
+  await program.methods
+    .addAttester(Array.from(manufacturerId), attester)
+    .accounts({
+      manufacturer: manufacturer.publicKey,
+      manufacturerAccount: findPda([Buffer.from("manufacturer"), manufacturerId], program.programId),
+      attesterAccount,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([manufacturer])
+    .rpc();
+
+  return attesterAccount;
+}
@@ -0,0 +1,1551 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { ShiftAttestation } from "../target/types/shift_attestation";
+import { assert } from "chai";
+import { createHash } from "crypto";
+import { readFileSync } from "fs";
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  Ed25519Program,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  SYSVAR_SLOT_HASHES_PUBKEY
+} from "@solana/web3.js";
+import {
+  governanceSigners,
+  governanceExecutor,
+  findPda,
+  attestationAuthorityPda,
+  setupGovernance,
+  passProposal,
+  executeGovernanceUpdate,
+  addManufacturer,
+  addAttester,
+  airdrop,
+  measurementDeprecationPda,
+  deviceSeriesPda,
+  u32Seed,
+  verifyAttestationAccounts,
+  deprecateMeasurementSet,
+} from "./fixtures";
+import { attestationQuoteHash, measurementSetId as measurementSetIdOf, sleep } from "../sdk/utils";
+
+describe("shift-attestation", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+
+  const program = anchor.workspace.ShiftAttestation as Program<ShiftAttestation>;
+  const provider = anchor.getProvider();
+
+  let attestationAuthority: PublicKey;
+  let manufacturerAccount: PublicKey;
+  let cosignManufacturerAccount: PublicKey;
+
+  // Test keypairs
+  const manufacturer = Keypair.generate();
+  const cosignManufacturer = Keypair.generate(); // Requires a co-signature on new attestations
+  const attester = Keypair.generate();
+  const deviceOwner = Keypair.generate();
+
+  // Test data
+  const manufacturerId = new Uint8Array(32).fill(30, 0, 32);
+  const cosignManufacturerId = new Uint8Array(32).fill(31, 0, 32);
+
+  const DEFAULT_MAX_VALIDITY_PERIOD = 86400 * 30;
+
+  const assertFails = async (attempt: Promise<unknown>, expectedError: string) => {
+    try {
+      await attempt;
+      assert.fail(`Should have failed with ${expectedError}`);
+    } catch (error) {
+      assert.ok(error.message.includes(expectedError), error.message);
+    }
+  };
+
+  const deviceIdFor = (seed: number) => new Uint8Array(32).fill(seed, 0, 32);
+
+  const attestationPda = (deviceId: Uint8Array) =>
+    findPda([Buffer.from("attestation"), deviceId], program.programId);
+
+  const quote = (deviceKey: Uint8Array = new Uint8Array(32).fill(3), measurementSeed = 5) => ({
+    version: 1,
+    signature: new Array(64).fill(2),
+    publicKey: Array.from(deviceKey),
+    nonce: new Array(32).fill(4),
+    timestamp: new anchor.BN(Math.floor(Date.now() / 1000)),
+    measurements: [new Array(32).fill(measurementSeed)],
+  });
+
+  // Create an attestation for `deviceId` as `signer`, a registered attester by default
+  const attest = (
+    deviceId: Uint8Array,
+    id: Uint8Array = manufacturerId,
+    signer: Keypair = attester,
+    attestationQuote: any = quote(),
+    deviceCertificate: number[] = new Array(1024).fill(6)
+  ) =>
+    program.methods
+      .createAttestation(Array.from(deviceId), Array.from(id), deviceOwner.publicKey, attestationQuote, deviceCertificate)
+      .accounts({
+        attester: signer.publicKey,
+        attestationRecord: attestationPda(deviceId),
+        deviceSeries: deviceSeriesPda(deviceId),
+        manufacturerAccount: findPda([Buffer.from("manufacturer"), id], program.programId),
+        attesterAccount: findPda([Buffer.from("attester"), id, signer.publicKey.toBuffer()], program.programId),
+        attestationAuthority,
+        measurementDeprecation: measurementDeprecationPda(attestationQuote.measurements),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([signer])
+      .rpc();
+
+  // Refresh `deviceId` with a new quote signed by `deviceKey`, submitted by `refresher`.
+  // The refresher's attester registration is passed along when it has one.
+  const refresh = async (
+    deviceId: Uint8Array,
+    deviceKey: Keypair,
+    measurementSeed: number,
+    refresher: Keypair = deviceOwner,
+    signingKey: Keypair = deviceKey
+  ) => {
+    const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+    const newQuote = quote(deviceKey.publicKey.toBytes(), measurementSeed);
+    const signatureIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: signingKey.secretKey,
+      message: attestationQuoteHash(deviceId, newQuote),
+    });
+    newQuote.signature = Array.from(signatureIx.data.subarray(16 + 32, 16 + 32 + 64));
+    const historyLength = Buffer.alloc(4);
+    historyLength.writeUInt32LE(record.historyLength);
+    const attesterAccount = findPda(
+      [Buffer.from("attester"), record.manufacturerId, refresher.publicKey.toBuffer()],
+      program.programId
+    );
+    const registered = await program.account.attesterAccount.fetchNullable(attesterAccount);
+
+    return program.methods
+      .refreshAttestation(Array.from(deviceId), newQuote)
+      .accounts({
+        refresher: refresher.publicKey,
+        attestationRecord: attestationPda(deviceId),
+        historyEntry: findPda(
+          [
+            Buffer.from("attestation_history"),
+            deviceId,
+            u32Seed(record.series),
+            historyLength,
+          ],
+          program.programId
+        ),
+        manufacturerAccount: findPda([Buffer.from("manufacturer"), record.manufacturerId], program.programId),
+        attestationAuthority,
+        attesterAccount: registered ? attesterAccount : null,
+        measurementDeprecation: measurementDeprecationPda(newQuote.measurements),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([signatureIx])
+      .signers([refresher])
+      .rpc();
+  };
+
+  // Latest slot in the SlotHashes sysvar, and the challenge hardware evidence for `deviceId`
+  // answers there
+  const latestChallenge = async (deviceId: Uint8Array) => {
+    const slotHashes = (await provider.connection.getAccountInfo(SYSVAR_SLOT_HASHES_PUBKEY)).data;
+    const slot = slotHashes.subarray(8, 16);
+    const nonce = createHash("sha256")
+      .update("SHIFT_DEVICE_CHALLENGE")
+      .update(deviceId)
+      .update(slot)
+      .update(slotHashes.subarray(16, 48))
+      .digest();
+    return { slot: new anchor.BN(slot, "le"), nonce };
+  };
+
+  // Simulate verify_attestation for `deviceId`
+  const verify = async (deviceId: Uint8Array) =>
+    program.methods
+      .verifyAttestation(Array.from(deviceId))
+      .accounts(await verifyAttestationAccounts(deviceId))
+      .view();
+
+  // Pass and execute a record-level governance action
+  const revoke = async (deviceId: Uint8Array, reason: any) => {
+    const proposal = await passProposal({ revokeAttestation: { deviceId: Array.from(deviceId), reason } });
+    await program.methods
+      .revokeAttestation(Array.from(deviceId), reason)
+      .accounts({
+        executor: governanceExecutor.publicKey,
+        attestationRecord: attestationPda(deviceId),
+        attestationAuthority,
+        proposal,
+      })
+      .signers([governanceExecutor])
+      .rpc();
+  };
+
+  const reinstate = async (deviceId: Uint8Array) => {
+    const proposal = await passProposal({ reinstateAttestation: { deviceId: Array.from(deviceId) } });
+    await program.methods
+      .reinstateAttestation(Array.from(deviceId))
+      .accounts({
+        executor: governanceExecutor.publicKey,
+        attestationRecord: attestationPda(deviceId),
+        attestationAuthority,
+        proposal,
+      })
+      .signers([governanceExecutor])
+      .rpc();
+  };
+
+  const setPolicy = (id: Uint8Array, signer: Keypair, policy: Partial<Record<string, any>>) =>
+    program.methods
+      .setAttestationPolicy(Array.from(id), {
+        validityPeriod: new anchor.BN(DEFAULT_MAX_VALIDITY_PERIOD),
+        gracePeriod: new anchor.BN(0),
+        requireCosign: false,
+        endorsementThreshold: 1,
+        ...policy,
+      })
+      .accounts({
+        manufacturer: signer.publicKey,
+        manufacturerAccount: findPda([Buffer.from("manufacturer"), id], program.programId),
+        attestationAuthority,
+      })
+      .signers([signer])
+      .rpc();
+
+  before(async () => {
+    await setupGovernance();
+    await airdrop(manufacturer, cosignManufacturer, attester, deviceOwner);
+    attestationAuthority = attestationAuthorityPda();
+
+    manufacturerAccount = await addManufacturer(manufacturerId, manufacturer.publicKey);
+    await addAttester(manufacturerId, manufacturer, attester.publicKey);
+
+    cosignManufacturerAccount = await addManufacturer(cosignManufacturerId, cosignManufacturer.publicKey);
+    await addAttester(cosignManufacturerId, cosignManufacturer, attester.publicKey);
+    await setPolicy(cosignManufacturerId, cosignManufacturer, { requireCosign: true });
+  });
+
+  describe("governance", () => {
+    // Execute a SetMaxValidityPeriod proposal; the period is left unchanged for other tests
+    const setMaxValidityPeriod = (proposal: PublicKey, maxValidityPeriod = DEFAULT_MAX_VALIDITY_PERIOD) =>
+      program.methods
+        .setMaxValidityPeriod(new anchor.BN(maxValidityPeriod))
+        .accounts({
+          executor: governanceExecutor.publicKey,
+          attestationAuthority,
+          proposal,
+        })
+        .signers([governanceExecutor])
+        .rpc();
+
+    const maxValidityAction = (maxValidityPeriod = DEFAULT_MAX_VALIDITY_PERIOD) => ({
+      setMaxValidityPeriod: { maxValidityPeriod: new anchor.BN(maxValidityPeriod) },
+    });
+
+    const cancelProposal = async (proposal: PublicKey, canceller: Keypair) => {
+      const { proposalId, proposer } = await program.account.proposal.fetch(proposal);
+      return program.methods
+        .cancelProposal(proposalId)
+        .accounts({ canceller: canceller.publicKey, proposal, proposer, attestationAuthority })
+        .signers([canceller])
+        .rpc();
+    };
+
+    it("Rejects out-of-range parameters when they are proposed", async () => {
+      await assertFails(passProposal(maxValidityAction(0), []), "InvalidValidityPolicy");
+      await assertFails(passProposal(maxValidityAction(86400 * 366), []), "InvalidValidityPolicy");
+      await assertFails(
+        passProposal(
+          {
+            rotateManufacturerKey: {
+              manufacturerId: Array.from(manufacturerId),
+              newPublicKey: new Array(32).fill(0),
+              overlapPeriod: new anchor.BN(0),
+            },
+          },
+          []
+        ),
+        "InvalidManufacturerKey"
+      );
+    });
+
+    it("Lets only the proposer cancel a current proposal, refunding its rent", async () => {
+      const proposal = await passProposal(maxValidityAction(), []);
+      await assertFails(cancelProposal(proposal, governanceSigners[1]), "UnauthorizedCancellation");
+
+      const balanceBefore = await provider.connection.getBalance(governanceExecutor.publicKey);
+      await cancelProposal(proposal, governanceExecutor);
+
+      assert.isNull(await program.account.proposal.fetchNullable(proposal));
+      const balanceAfter = await provider.connection.getBalance(governanceExecutor.publicKey);
+      assert.isAbove(balanceAfter, balanceBefore);
+    });
+
+    it("Refuses to execute a proposal below the approval threshold", async () => {
+      const proposal = await passProposal(maxValidityAction(), []);
+      await assertFails(setMaxValidityPeriod(proposal), "InsufficientApprovals");
+    });
+
+    it("Rejects a second approval by the same signer", async () => {
+      const proposal = await passProposal(maxValidityAction(), []);
+      const { proposalId } = await program.account.proposal.fetch(proposal);
+
+      await assertFails(
+        program.methods
+          .approveProposal(proposalId)
+          .accounts({ approver: governanceExecutor.publicKey, proposal, attestationAuthority })
+          .signers([governanceExecutor])
+          .rpc(),
+        "AlreadyApproved"
+      );
+    });
+
+    it("Executes a proposal at the threshold and closes it to the executor", async () => {
+      const proposal = await passProposal(maxValidityAction());
+      const balanceBefore = await provider.connection.getBalance(governanceExecutor.publicKey);
+
+      await setMaxValidityPeriod(proposal);
+
+      assert.isNull(await program.account.proposal.fetchNullable(proposal));
+      const balanceAfter = await provider.connection.getBalance(governanceExecutor.publicKey);
+      assert.isAbove(balanceAfter, balanceBefore);
+    });
+
+    it("Rejects replaying an executed proposal", async () => {
+      const proposal = await passProposal(maxValidityAction());
+      await setMaxValidityPeriod(proposal);
+
+      await assertFails(setMaxValidityPeriod(proposal), "AccountNotInitialized");
+    });
+
+    it("Rejects a proposal used for a different action", async () => {
+      const proposal = await passProposal(maxValidityAction());
+
+      await assertFails(setMaxValidityPeriod(proposal, DEFAULT_MAX_VALIDITY_PERIOD + 1), "ProposalActionMismatch");
+      await assertFails(
+        program.methods
+          .deprecateMeasurementSet(new Array(32).fill(1))
+          .accounts({
+            executor: governanceExecutor.publicKey,
+            deprecatedMeasurementSet: findPda(
+              [Buffer.from("deprecated_measurements"), new Uint8Array(32).fill(1)],
+              program.programId
+            ),
+            attestationAuthority,
+            proposal,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([governanceExecutor])
+          .rpc(),
+        "ProposalActionMismatch"
+      );
+
+      await setMaxValidityPeriod(proposal);
+    });
+
+    it("Invalidates proposals approved under a previous signer set", async () => {
+      const staleProposal = await passProposal(maxValidityAction());
+      const { signerSetVersion } = await program.account.attestationAuthority.fetch(attestationAuthority);
+
+      // Re-installing the same signers still starts a new signer set version
+      await executeGovernanceUpdate({
+        setSigners: {
+          signers: governanceSigners.map((signer) => signer.publicKey),
+          threshold: governanceSigners.length - 1,
+        },
+      });
+
+      const authority = await program.account.attestationAuthority.fetch(attestationAuthority);
+      assert.equal(authority.signerSetVersion, signerSetVersion + 1);
+      await assertFails(setMaxValidityPeriod(staleProposal), "ProposalStale");
+
+      // Any signer can clear it away
+      await cancelProposal(staleProposal, governanceSigners[1]);
+      assert.isNull(await program.account.proposal.fetchNullable(staleProposal));
+    });
+
+    it("Rejects governance actions from outside the signer set", async () => {
+      const outsider = Keypair.generate();
+      const proposal = await passProposal(maxValidityAction());
+
+      await assertFails(
+        program.methods
+          .setMaxValidityPeriod(new anchor.BN(DEFAULT_MAX_VALIDITY_PERIOD))
+          .accounts({ executor: outsider.publicKey, attestationAuthority, proposal })
+          .signers([outsider])
+          .rpc(),
+        "NotGovernanceSigner"
+      );
+
+      await setMaxValidityPeriod(proposal);
+    });
+
+    it("Holds manufacturer additions until the timelock elapses", async () => {
+      await executeGovernanceUpdate({ setManufacturerTimelock: { manufacturerTimelock: new anchor.BN(3600) } });
+
+      try {
+        await assertFails(
+          addManufacturer(new Uint8Array(32).fill(200), Keypair.generate().publicKey),
+          "ProposalTimelocked"
+        );
+
+        const authority = await program.account.attestationAuthority.fetch(attestationAuthority);
+        const proposal = await program.account.proposal.fetch(
+          findPda(
+            [Buffer.from("proposal"), Buffer.from(authority.proposalCount.subn(1).toArray("le", 8))],
+            program.programId
+          )
+        );
+        assert.equal(proposal.executableAt.sub(proposal.createdAt).toNumber(), 3600);
+      } finally {
+        await executeGovernanceUpdate({ setManufacturerTimelock: { manufacturerTimelock: new anchor.BN(0) } });
+      }
+    });
+  });
+
+  describe("revocation", () => {
+    it("Reinstates a record revoked for a reversible reason", async () => {
+      const deviceId = deviceIdFor(40);
+      await attest(deviceId);
+
+      await revoke(deviceId, { lost: {} });
+      let record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.status, { revoked: {} });
+      assert.deepEqual(record.statusBeforeRevocation, { valid: {} });
+
+      await reinstate(deviceId);
+      record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.status, { valid: {} });
+      assert.isNull(record.revocationReason);
+      assert.isNull(record.statusBeforeRevocation);
+    });
+
+    it("Reinstates a pending record as pending, not valid", async () => {
+      const deviceId = deviceIdFor(41);
+      await attest(deviceId, cosignManufacturerId);
+
+      await revoke(deviceId, { userRequested: {} });
+      await reinstate(deviceId);
+
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.status, { pending: {} });
+    });
+
+    it("Refuses to reinstate a record revoked as compromised", async () => {
+      const deviceId = deviceIdFor(42);
+      await attest(deviceId);
+      await revoke(deviceId, { compromised: {} });
+
+      await assertFails(reinstate(deviceId), "RevocationNotReversible");
+    });
+  });
+
+  describe("endorsements", () => {
+    // Manufacturer whose attestations need the creator and two more attesters
+    const endorsedManufacturer = Keypair.generate();
+    const endorsedManufacturerId = new Uint8Array(32).fill(32, 0, 32);
+    const endorsers = [Keypair.generate(), Keypair.generate()];
+
+    const endorse = (deviceId: Uint8Array, endorser: Keypair, quoteHash: Uint8Array) =>
+      program.account.attestationRecord.fetch(attestationPda(deviceId)).then((record) =>
+        program.methods
+          .endorseAttestation(Array.from(deviceId), Array.from(quoteHash))
+          .accounts({
+            endorser: endorser.publicKey,
+            attestationRecord: attestationPda(deviceId),
+            pendingEndorsement: findPda(
+              [Buffer.from("endorsement"), deviceId, u32Seed(record.series)],
+              program.programId
+            ),
+            manufacturerAccount: findPda([Buffer.from("manufacturer"), endorsedManufacturerId], program.programId),
+            attesterAccount: findPda(
+              [Buffer.from("attester"), endorsedManufacturerId, endorser.publicKey.toBuffer()],
+              program.programId
+            ),
+            attestationAuthority,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([endorser])
+          .rpc()
+      );
+
+    // Attest `deviceId` under the endorsed manufacturer, returning the quote hash to endorse
+    const attestPending = async (deviceId: Uint8Array) => {
+      const attestationQuote = quote();
+      await attest(deviceId, endorsedManufacturerId, attester, attestationQuote);
+      return attestationQuoteHash(deviceId, attestationQuote);
+    };
+
+    before(async () => {
+      await airdrop(endorsedManufacturer, ...endorsers);
+      await addManufacturer(endorsedManufacturerId, endorsedManufacturer.publicKey);
+      for (const registered of [attester, ...endorsers]) {
+        await addAttester(endorsedManufacturerId, endorsedManufacturer, registered.publicKey);
+      }
+      await setPolicy(endorsedManufacturerId, endorsedManufacturer, { endorsementThreshold: 3 });
+    });
+
+    it("Activates an attestation once the endorsement threshold is met", async () => {
+      const deviceId = deviceIdFor(50);
+      const quoteHash = await attestPending(deviceId);
+
+      await endorse(deviceId, endorsers[0], quoteHash);
+      let record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.status, { pending: {} });
+      assert.equal(record.endorsements, 2);
+
+      await endorse(deviceId, endorsers[1], quoteHash);
+      record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.status, { valid: {} });
+      assert.equal(record.endorsements, 3);
+    });
+
+    it("Rejects a second endorsement by the same attester", async () => {
+      const deviceId = deviceIdFor(51);
+      const quoteHash = await attestPending(deviceId);
+
+      await endorse(deviceId, endorsers[0], quoteHash);
+      await assertFails(endorse(deviceId, endorsers[0], quoteHash), "DuplicateEndorsement");
+    });
+
+    it("Rejects the creating attester endorsing its own attestation", async () => {
+      const deviceId = deviceIdFor(52);
+      const quoteHash = await attestPending(deviceId);
+
+      await assertFails(endorse(deviceId, attester, quoteHash), "DuplicateEndorsement");
+    });
+
+    it("Rejects endorsements of a different quote", async () => {
+      const deviceId = deviceIdFor(53);
+      await attestPending(deviceId);
+
+      await assertFails(endorse(deviceId, endorsers[0], new Uint8Array(32).fill(1)), "EndorsementQuoteMismatch");
+    });
+  });
+
+  describe("measurement deprecation", () => {
+    const deviceKey = Keypair.generate();
+
+    it("Stops verifying records on a deprecated measurement set", async () => {
+      const deviceId = deviceIdFor(60);
+      await attest(deviceId, manufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 60));
+      assert.deepEqual((await verify(deviceId)).validity, { valid: {} });
+
+      await deprecateMeasurementSet([new Array(32).fill(60)]);
+      await assertFails(verify(deviceId), "MeasurementSetDeprecated");
+    });
+
+    it("Rejects new attestations on a deprecated measurement set", async () => {
+      await assertFails(
+        attest(deviceIdFor(61), manufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 60)),
+        "MeasurementSetDeprecated"
+      );
+    });
+
+    it("Rejects refreshing onto a deprecated measurement set", async () => {
+      const deviceId = deviceIdFor(62);
+      await attest(deviceId, manufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 62));
+
+      await assertFails(refresh(deviceId, deviceKey, 60), "MeasurementSetDeprecated");
+      await refresh(deviceId, deviceKey, 63);
+    });
+
+    it("Rejects a deprecation account for a different measurement set", async () => {
+      const deviceId = deviceIdFor(64);
+      const attestationQuote = quote(deviceKey.publicKey.toBytes(), 64);
+
+      await assertFails(
+        program.methods
+          .createAttestation(Array.from(deviceId), Array.from(manufacturerId), deviceOwner.publicKey, attestationQuote, new Array(1024).fill(6))
+          .accounts({
+            attester: attester.publicKey,
+            attestationRecord: attestationPda(deviceId),
+            deviceSeries: deviceSeriesPda(deviceId),
+            manufacturerAccount,
+            attesterAccount: findPda([Buffer.from("attester"), manufacturerId, attester.publicKey.toBuffer()], program.programId),
+            attestationAuthority,
+            measurementDeprecation: measurementDeprecationPda([new Array(32).fill(65)]),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([attester])
+          .rpc(),
+        "MeasurementDeprecationMismatch"
+      );
+    });
+  });
+
+  describe("stale pending attestations", () => {
+    // Manufacturer whose attestations need a co-signature and lapse after a second
+    const shortLivedManufacturer = Keypair.generate();
+    const shortLivedManufacturerId = new Uint8Array(32).fill(33, 0, 32);
+
+    const closeStale = (deviceId: Uint8Array) =>
+      program.methods
+        .closeStaleAttestation(Array.from(deviceId))
+        .accounts({
+          attestationRecord: attestationPda(deviceId),
+          manufacturerAccount: findPda([Buffer.from("manufacturer"), shortLivedManufacturerId], program.programId),
+          attestationAuthority,
+          attester: attester.publicKey,
+        })
+        .rpc();
+
+    before(async () => {
+      await airdrop(shortLivedManufacturer);
+      await addManufacturer(shortLivedManufacturerId, shortLivedManufacturer.publicKey);
+      await addAttester(shortLivedManufacturerId, shortLivedManufacturer, attester.publicKey);
+      await setPolicy(shortLivedManufacturerId, shortLivedManufacturer, {
+        validityPeriod: new anchor.BN(1),
+        requireCosign: true,
+      });
+    });
+
+    it("Closes a pending attestation once it has lapsed, refunding the attester", async () => {
+      const deviceId = deviceIdFor(70);
+      await attest(deviceId, shortLivedManufacturerId);
+      await assertFails(closeStale(deviceId), "AttestationNotExpired");
+
+      await sleep(2000);
+      const balanceBefore = await provider.connection.getBalance(attester.publicKey);
+      const { totalAttestations } = await program.account.attestationAuthority.fetch(attestationAuthority);
+      await closeStale(deviceId);
+
+      assert.isNull(await program.account.attestationRecord.fetchNullable(attestationPda(deviceId)));
+      assert.isAbove(await provider.connection.getBalance(attester.publicKey), balanceBefore);
+      const authority = await program.account.attestationAuthority.fetch(attestationAuthority);
+      assert.equal(authority.totalAttestations.toNumber(), totalAttestations.toNumber() - 1);
+
+      // The device can be attested again
+      await attest(deviceId, shortLivedManufacturerId);
+    });
+
+    it("Does not close attestations that are not pending", async () => {
+      const deviceId = deviceIdFor(71);
+      await attest(deviceId);
+
+      await assertFails(
+        program.methods
+          .closeStaleAttestation(Array.from(deviceId))
+          .accounts({ attestationRecord: attestationPda(deviceId), manufacturerAccount, attestationAuthority, attester: attester.publicKey })
+          .rpc(),
+        "AttestationNotPending"
+      );
+    });
+  });
+
+  describe("Android key attestation", () => {
+    // Generated by programs/shift-attestation/testdata/generate_android.py
+    const strongBoxCertificate = readFileSync("programs/shift-attestation/testdata/android_strongbox_p256_v100.der");
+    const teeCertificate = readFileSync("programs/shift-attestation/testdata/android_tee_ed25519_v3.der");
+    const androidManufacturer = Keypair.generate();
+    const androidManufacturerId = new Uint8Array(32).fill(34, 0, 32);
+    const challenge = new Uint8Array(32).fill(0x42, 0, 32);
+    const bootHash = new Array(32).fill(0xb1);
+
+    // The certificate chain is checked off-chain, so the generated certificates can be reused
+    // with their challenge, verified boot state or boot hash patched in
+    const patched = (
+      certificate: Buffer,
+      { nonce, bootState, bootHashByte }: { nonce?: Uint8Array; bootState?: number; bootHashByte?: number }
+    ) => {
+      const copy = Buffer.from(certificate);
+      const replace = (from: Buffer, to: Buffer) => to.copy(copy, copy.indexOf(from));
+      if (nonce) replace(Buffer.from(challenge), Buffer.from(nonce));
+      // deviceLocked TRUE, then verifiedBootState ENUMERATED (Verified)
+      if (bootState !== undefined) replace(Buffer.from("0101ff0a0100", "hex"), Buffer.from([1, 1, 0xff, 0x0a, 1, bootState]));
+      if (bootHashByte !== undefined) replace(Buffer.from(bootHash), Buffer.alloc(32, bootHashByte));
+      return copy;
+    };
+
+    const attestAndroid = (deviceId: Uint8Array, certificate: Buffer) =>
+      program.methods
+        .createAttestationAndroid(
+          Array.from(deviceId),
+          Array.from(androidManufacturerId),
+          deviceOwner.publicKey,
+          certificate,
+          Array.from(challenge)
+        )
+        .accounts({
+          attester: attester.publicKey,
+          attestationRecord: attestationPda(deviceId),
+          deviceSeries: deviceSeriesPda(deviceId),
+          manufacturerAccount: findPda([Buffer.from("manufacturer"), androidManufacturerId], program.programId),
+          attesterAccount: findPda(
+            [Buffer.from("attester"), androidManufacturerId, attester.publicKey.toBuffer()],
+            program.programId
+          ),
+          attestationAuthority,
+          measurementDeprecation: measurementDeprecationPda([bootHash]),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([attester])
+        .rpc();
+
+    // Refresh `deviceId` from `certificate`, submitted by the device owner against the
+    // challenge at `challengeSlot`
+    const refreshAndroid = async (
+      deviceId: Uint8Array,
+      certificate: Buffer,
+      challengeSlot: anchor.BN,
+      boot: ArrayLike<number> = bootHash
+    ) => {
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      return program.methods
+        .refreshAttestationAndroid(Array.from(deviceId), certificate, challengeSlot)
+        .accounts({
+          refresher: deviceOwner.publicKey,
+          attestationRecord: attestationPda(deviceId),
+          historyEntry: findPda(
+            [Buffer.from("attestation_history"), deviceId, u32Seed(record.series), u32Seed(record.historyLength)],
+            program.programId
+          ),
+          manufacturerAccount: findPda([Buffer.from("manufacturer"), androidManufacturerId], program.programId),
+          attestationAuthority,
+          attesterAccount: null,
+          measurementDeprecation: measurementDeprecationPda([boot]),
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([deviceOwner])
+        .rpc();
+    };
+
+    before(async () => {
+      await airdrop(androidManufacturer);
+      await addManufacturer(androidManufacturerId, androidManufacturer.publicKey, { trustedExecutionEnvironment: {} });
+      await addAttester(androidManufacturerId, androidManufacturer, attester.publicKey);
+    });
+
+    it("Accepts a StrongBox certificate with a P-256 key", async () => {
+      const deviceId = deviceIdFor(80);
+      await attestAndroid(deviceId, strongBoxCertificate);
+
+      // The record holds the SHA-256 of the compressed P-256 key
+      const compressedKey = Buffer.from(
+        "037cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978",
+        "hex"
+      );
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.securityLevel, { strongBox: {} });
+      assert.deepEqual(record.deviceKeyAlgorithm, { ecdsaP256: {} });
+      assert.deepEqual(Array.from(record.deviceKey), Array.from(createHash("sha256").update(compressedKey).digest()));
+    });
+
+    it("Refreshes from a new certificate over the on-chain challenge, taking its verified boot state", async () => {
+      const deviceId = deviceIdFor(81);
+      await attestAndroid(deviceId, teeCertificate);
+
+      const { slot, nonce } = await latestChallenge(deviceId);
+      await refreshAndroid(deviceId, patched(teeCertificate, { nonce, bootState: 2 }), slot);
+
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.equal(record.historyLength, 1);
+      assert.deepEqual(record.verifiedBootState, { unverified: {} });
+      assert.deepEqual(Array.from(record.attestationQuote.nonce), Array.from(nonce));
+    });
+
+    it("Rejects a refresh certificate over an old challenge", async () => {
+      const deviceId = deviceIdFor(82);
+      await attestAndroid(deviceId, teeCertificate);
+
+      const { slot } = await latestChallenge(deviceId);
+      await assertFails(refreshAndroid(deviceId, teeCertificate, slot), "ChallengeMismatch");
+    });
+
+    it("Rejects refreshing onto a deprecated boot measurement", async () => {
+      const deviceId = deviceIdFor(83);
+      await attestAndroid(deviceId, teeCertificate);
+      const deprecatedBootHash = new Array(32).fill(0xb2);
+      await deprecateMeasurementSet([deprecatedBootHash]);
+
+      const { slot, nonce } = await latestChallenge(deviceId);
+      await assertFails(
+        refreshAndroid(deviceId, patched(teeCertificate, { nonce, bootHashByte: 0xb2 }), slot, deprecatedBootHash),
+        "MeasurementSetDeprecated"
+      );
+    });
+
+    it("Rejects a refresh certificate for another key", async () => {
+      const deviceId = deviceIdFor(84);
+      await attestAndroid(deviceId, teeCertificate);
+
+      const { slot, nonce } = await latestChallenge(deviceId);
+      await assertFails(refreshAndroid(deviceId, patched(strongBoxCertificate, { nonce }), slot), "DeviceKeyMismatch");
+    });
+
+    it("Rejects a Shift-format refresh of an Android attestation", async () => {
+      const deviceId = deviceIdFor(85);
+      await attestAndroid(deviceId, teeCertificate);
+
+      // generate_android.py's Ed25519 key, so only the quote format is wrong
+      await assertFails(refresh(deviceId, Keypair.fromSeed(new Uint8Array(32).fill(0x01)), 85), "QuoteFormatMismatch");
+    });
+  });
+
+  describe("TPM quotes", () => {
+    // Device certificates generated by programs/shift-attestation/testdata/generate_tpm.py
+    const testdata = (name: string) => readFileSync(`programs/shift-attestation/testdata/${name}`);
+    const deviceCertificate = testdata("tpm_device_certificate.der");
+
+    // generate_tpm.py's EdDSA attestation key, and the manufacturer key certifying it
+    const attestationKey = Keypair.fromSeed(new Uint8Array(32).fill(0x04));
+    const tpmManufacturer = Keypair.fromSeed(new Uint8Array(32).fill(0x05));
+    const tpmManufacturerId = new Uint8Array(32).fill(35, 0, 32);
+
+    const TPM_ALG_SHA1 = 0x0004;
+    const TPM_ALG_SHA256 = 0x000b;
+    const sha256 = (...parts: (Buffer | Uint8Array | string)[]) => {
+      const hash = createHash("sha256");
+      parts.forEach((part) => hash.update(part));
+      return hash.digest();
+    };
+    const be = (value: number, bytes: number) => {
+      const buffer = Buffer.alloc(bytes);
+      buffer.writeUIntBE(value, 0, bytes);
+      return buffer;
+    };
+    const tpm2b = (data: Buffer | Uint8Array) => Buffer.concat([be(data.length, 2), data]);
+    const pcrDigest = sha256(...Array.from({ length: 8 }, (_, i) => Buffer.alloc(32, i)));
+
+    // A quote over `nonce` from `signer`, laid out as generate_tpm.py lays out TPMS_ATTEST:
+    // its TPMT_SIGNATURE, the Ed25519 program instruction checking it over SHA-256(TPMS_ATTEST),
+    // and the measurements the record takes from it (the PCR selection, then the PCR digest)
+    const tpmQuote = (
+      nonce: Uint8Array,
+      { pcrSelect = [0xff, 0x00, 0x00], hashAlg = TPM_ALG_SHA256, signer = attestationKey } = {}
+    ) => {
+      const pcrSelection = Buffer.concat([be(hashAlg, 2), Buffer.from([pcrSelect.length, ...pcrSelect])]);
+      const tpmsAttest = Buffer.concat([
+        be(0xff544347, 4), // TPM_GENERATED_VALUE
+        be(0x8018, 2), // TPM_ST_ATTEST_QUOTE
+        tpm2b(Buffer.concat([be(TPM_ALG_SHA256, 2), sha256("attestation key")])),
+        tpm2b(nonce),
+        Buffer.concat([be(1_000_000, 8), be(3, 4), be(0, 4), Buffer.from([1])]), // TPMS_CLOCK_INFO
+        Buffer.from("0001000200030004", "hex"), // Firmware version
+        be(1, 4),
+        pcrSelection,
+        tpm2b(pcrDigest),
+      ]);
+      const signatureIx = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: signer.secretKey,
+        message: sha256(tpmsAttest),
+      });
+      const signature = signatureIx.data.subarray(16 + 32, 16 + 32 + 64);
+      // TPMT_SIGNATURE is sigAlg (EdDSA), hashAlg, then size-prefixed R and S
+      const tpmtSignature = Buffer.concat([
+        be(0x0060, 2),
+        be(TPM_ALG_SHA256, 2),
+        tpm2b(signature.subarray(0, 32)),
+        tpm2b(signature.subarray(32)),
+      ]);
+      const measurements = [sha256("SHIFT_TPM_PCR_SELECTION", pcrSelection), pcrDigest];
+      return { tpmsAttest, tpmtSignature, signatureIx, measurements };
+    };
+
+    // Ed25519 program instruction for the manufacturer's signature over a device certificate's
+    // TBSCertificate. The test certificates are Ed25519-signed, so the signature is the last 64 bytes.
+    const certificateIx = (certificate: Buffer = deviceCertificate) => {
+      // DER header and content lengths of the element at `offset`
+      const element = (offset: number) => {
+        const first = certificate[offset + 1];
+        if (first < 0x80) return { header: 2, content: first };
+        const lengthBytes = first & 0x7f;
+        return { header: 2 + lengthBytes, content: certificate.readUIntBE(offset + 2, lengthBytes) };
+      };
+      const tbsStart = element(0).header;
+      const tbs = element(tbsStart);
+      return Ed25519Program.createInstructionWithPublicKey({
+        publicKey: tpmManufacturer.publicKey.toBytes(),
+        message: certificate.subarray(tbsStart, tbsStart + tbs.header + tbs.content),
+        signature: certificate.subarray(certificate.length - 64),
+      });
+    };
+
+    const padded = (certificate: Buffer) => Array.from(Buffer.concat([certificate, Buffer.alloc(1024 - certificate.length)]));
+
+    const attestTpm = (
+      deviceId: Uint8Array,
+      quote: ReturnType<typeof tpmQuote>,
+      challengeSlot: anchor.BN,
+      preInstructions: anchor.web3.TransactionInstruction[] = [certificateIx(), quote.signatureIx],
+      certificate: Buffer = deviceCertificate
+    ) =>
+      program.methods
+        .createAttestationTpm(
+          Array.from(deviceId),
+          Array.from(tpmManufacturerId),
+          deviceOwner.publicKey,
+          { ed25519: [Array.from(attestationKey.publicKey.toBytes())] },
+          quote.tpmsAttest,
+          quote.tpmtSignature,
+          challengeSlot,
+          padded(certificate)
+        )
+        .accounts({
+          attester: attester.publicKey,
+          attestationRecord: attestationPda(deviceId),
+          deviceSeries: deviceSeriesPda(deviceId),
+          manufacturerAccount: findPda([Buffer.from("manufacturer"), tpmManufacturerId], program.programId),
+          attesterAccount: findPda(
+            [Buffer.from("attester"), tpmManufacturerId, attester.publicKey.toBuffer()],
+            program.programId
+          ),
+          attestationAuthority,
+          measurementDeprecation: measurementDeprecationPda(quote.measurements),
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions(preInstructions)
+        .signers([attester])
+        .rpc();
+
+    // Refresh `deviceId` from a quote over its current challenge, submitted by the device owner
+    const refreshTpm = async (deviceId: Uint8Array, options: Parameters<typeof tpmQuote>[1] = {}) => {
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      const { slot, nonce } = await latestChallenge(deviceId);
+      const quote = tpmQuote(nonce, options);
+      return program.methods
+        .refreshAttestationTpm(
+          Array.from(deviceId),
+          { ed25519: [Array.from(attestationKey.publicKey.toBytes())] },
+          quote.tpmsAttest,
+          quote.tpmtSignature,
+          slot
+        )
+        .accounts({
+          refresher: deviceOwner.publicKey,
+          attestationRecord: attestationPda(deviceId),
+          historyEntry: findPda(
+            [Buffer.from("attestation_history"), deviceId, u32Seed(record.series), u32Seed(record.historyLength)],
+            program.programId
+          ),
+          manufacturerAccount: findPda([Buffer.from("manufacturer"), tpmManufacturerId], program.programId),
+          attestationAuthority,
+          attesterAccount: null,
+          measurementDeprecation: measurementDeprecationPda(quote.measurements),
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions([quote.signatureIx])
+        .signers([deviceOwner])
+        .rpc();
+    };
+
+    // Attest `deviceId` from a valid quote over the default PCR selection
+    const attestTpmDevice = async (deviceId: Uint8Array) => {
+      const { slot, nonce } = await latestChallenge(deviceId);
+      await attestTpm(deviceId, tpmQuote(nonce), slot);
+    };
+
+    before(async () => {
+      await airdrop(tpmManufacturer);
+      await addManufacturer(tpmManufacturerId, tpmManufacturer.publicKey, { trustedExecutionEnvironment: {} });
+      await addAttester(tpmManufacturerId, tpmManufacturer, attester.publicKey);
+    });
+
+    it("Accepts a quote over the on-chain challenge from a certified attestation key", async () => {
+      const deviceId = deviceIdFor(90);
+      const { slot, nonce } = await latestChallenge(deviceId);
+      const quote = tpmQuote(nonce);
+      await attestTpm(deviceId, quote, slot);
+
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.quoteFormat, { tpm2: {} });
+      assert.deepEqual(record.deviceKeyAlgorithm, { ed25519: {} });
+      assert.deepEqual(Array.from(record.attestationQuote.nonce), Array.from(nonce));
+      assert.deepEqual(
+        record.attestationQuote.measurements.map((measurement) => Array.from(measurement)),
+        quote.measurements.map((measurement) => Array.from(measurement))
+      );
+    });
+
+    it("Rejects a quote over another device's challenge", async () => {
+      const { slot, nonce } = await latestChallenge(deviceIdFor(191));
+      await assertFails(attestTpm(deviceIdFor(91), tpmQuote(nonce), slot), "ChallengeMismatch");
+    });
+
+    it("Rejects a challenge slot missing from the slot hashes", async () => {
+      const deviceId = deviceIdFor(91);
+      const { slot, nonce } = await latestChallenge(deviceId);
+      await assertFails(attestTpm(deviceId, tpmQuote(nonce), slot.addn(10_000)), "UnknownChallengeSlot");
+    });
+
+    it("Rejects a quote without a SHA-256 PCR bank", async () => {
+      const deviceId = deviceIdFor(92);
+      const { slot, nonce } = await latestChallenge(deviceId);
+      await assertFails(attestTpm(deviceId, tpmQuote(nonce, { hashAlg: TPM_ALG_SHA1 }), slot), "InvalidTpmQuote");
+    });
+
+    it("Rejects a quote with trailing bytes", async () => {
+      const deviceId = deviceIdFor(93);
+      const { slot, nonce } = await latestChallenge(deviceId);
+      const quote = tpmQuote(nonce);
+      quote.tpmsAttest = Buffer.concat([quote.tpmsAttest, Buffer.from([0])]);
+      await assertFails(attestTpm(deviceId, quote, slot), "InvalidTpmQuote");
+    });
+
+    it("Rejects a quote whose signature was not checked by the attestation key", async () => {
+      const deviceId = deviceIdFor(94);
+      const { slot, nonce } = await latestChallenge(deviceId);
+      const otherSigner = tpmQuote(nonce, { signer: Keypair.generate() });
+
+      await assertFails(attestTpm(deviceId, otherSigner, slot), "InvalidQuoteSignature");
+      await assertFails(attestTpm(deviceId, tpmQuote(nonce), slot, [certificateIx()]), "InvalidQuoteSignature");
+    });
+
+    it("Rejects an attestation key the manufacturer has not certified", async () => {
+      const deviceId = deviceIdFor(95);
+      const { slot, nonce } = await latestChallenge(deviceId);
+      const quote = tpmQuote(nonce);
+      const otherKeyCertificate = testdata("tpm_device_certificate_other_key.der");
+
+      await assertFails(
+        attestTpm(deviceId, quote, slot, [certificateIx(otherKeyCertificate), quote.signatureIx], otherKeyCertificate),
+        "InvalidDeviceCertificate"
+      );
+      await assertFails(attestTpm(deviceId, quote, slot, [quote.signatureIx]), "InvalidDeviceCertificate");
+    });
+
+    it("Measures the PCR selection along with the PCR digest", async () => {
+      const deviceId = deviceIdFor(96);
+      const { slot, nonce } = await latestChallenge(deviceId);
+      await attestTpm(deviceId, tpmQuote(nonce, { pcrSelect: [0x0f, 0x00, 0x00] }), slot);
+
+      const fewerPcrs = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      const allPcrs = await program.account.attestationRecord.fetch(attestationPda(deviceIdFor(90)));
+      assert.deepEqual(fewerPcrs.attestationQuote.measurements[1], allPcrs.attestationQuote.measurements[1]);
+      assert.notDeepEqual(fewerPcrs.measurementSetId, allPcrs.measurementSetId);
+    });
+
+    it("Refreshes from a new quote, checking its PCRs against deprecations", async () => {
+      const deviceId = deviceIdFor(97);
+      await attestTpmDevice(deviceId);
+
+      const deprecated = tpmQuote(new Uint8Array(32), { pcrSelect: [0x03, 0x00, 0x00] });
+      await deprecateMeasurementSet(deprecated.measurements);
+      await assertFails(refreshTpm(deviceId, { pcrSelect: [0x03, 0x00, 0x00] }), "MeasurementSetDeprecated");
+
+      await refreshTpm(deviceId);
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.equal(record.historyLength, 1);
+      assert.deepEqual(record.status, { valid: {} });
+    });
+
+    it("Rejects a Shift-format refresh of a TPM attestation", async () => {
+      const deviceId = deviceIdFor(98);
+      await attestTpmDevice(deviceId);
+
+      await assertFails(refresh(deviceId, attestationKey, 98), "QuoteFormatMismatch");
+    });
+  });
+
+  describe("manufacturer key rotation", () => {
+    const rotatingManufacturer = Keypair.generate();
+    const rotatingManufacturerId = new Uint8Array(32).fill(36, 0, 32);
+    const newManufacturerKey = Keypair.generate();
+    const deviceKey = Keypair.generate();
+
+    const rotateKey = async (newPublicKey: PublicKey, overlapPeriod: number) => {
+      const action = {
+        manufacturerId: Array.from(rotatingManufacturerId),
+        newPublicKey: Array.from(newPublicKey.toBytes()),
+        overlapPeriod: new anchor.BN(overlapPeriod),
+      };
+      const proposal = await passProposal({ rotateManufacturerKey: action });
+      await program.methods
+        .rotateManufacturerKey(action.manufacturerId, action.newPublicKey, action.overlapPeriod)
+        .accounts({
+          executor: governanceExecutor.publicKey,
+          manufacturerAccount: findPda([Buffer.from("manufacturer"), rotatingManufacturerId], program.programId),
+          attestationAuthority,
+          proposal,
+        })
+        .signers([governanceExecutor])
+        .rpc();
+    };
+
+    before(async () => {
+      await airdrop(rotatingManufacturer);
+      await addManufacturer(rotatingManufacturerId, rotatingManufacturer.publicKey);
+      await addAttester(rotatingManufacturerId, rotatingManufacturer, attester.publicKey);
+    });
+
+    it("Rejects an overlap period beyond the maximum", async () => {
+      await assertFails(rotateKey(newManufacturerKey.publicKey, 86400 * 30 + 1), "InvalidOverlapPeriod");
+    });
+
+    it("Rejects rotating to the current key", async () => {
+      await assertFails(rotateKey(rotatingManufacturer.publicKey, 0), "InvalidManufacturerKey");
+    });
+
+    it("Trusts the previous key until the overlap ends, and a refresh moves to the new key", async () => {
+      const deviceId = deviceIdFor(100);
+      await attest(deviceId, rotatingManufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 100));
+
+      await rotateKey(newManufacturerKey.publicKey, 2);
+      const manufacturerState = await program.account.manufacturerAccount.fetch(
+        findPda([Buffer.from("manufacturer"), rotatingManufacturerId], program.programId)
+      );
+      assert.deepEqual(manufacturerState.previousPublicKey, Array.from(rotatingManufacturer.publicKey.toBytes()));
+      assert.deepEqual((await verify(deviceId)).validity, { valid: {} });
+
+      await sleep(4000);
+      await assertFails(verify(deviceId), "ManufacturerKeyRetired");
+
+      await refresh(deviceId, deviceKey, 101);
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.manufacturerKey, Array.from(newManufacturerKey.publicKey.toBytes()));
+      assert.deepEqual((await verify(deviceId)).validity, { valid: {} });
+    });
+  });
+
+  describe("refresh", () => {
+    const deviceKey = Keypair.generate();
+    const otherKey = Keypair.generate();
+    const stranger = Keypair.generate();
+
+    before(async () => {
+      await airdrop(stranger);
+    });
+
+    it("Lets the device owner, manufacturer and a registered attester refresh", async () => {
+      const deviceId = deviceIdFor(110);
+      await attest(deviceId, manufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 110));
+
+      await refresh(deviceId, deviceKey, 111, deviceOwner);
+      await refresh(deviceId, deviceKey, 112, manufacturer);
+      await refresh(deviceId, deviceKey, 113, attester);
+
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.equal(record.historyLength, 3);
+      assert.deepEqual(Array.from(record.attestationQuote.measurements[0]), new Array(32).fill(113));
+    });
+
+    it("Rejects a refresh from anyone else", async () => {
+      const deviceId = deviceIdFor(114);
+      await attest(deviceId, manufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 110));
+
+      await assertFails(refresh(deviceId, deviceKey, 111, stranger), "UnauthorizedRefresh");
+    });
+
+    it("Rejects a quote for a different device key", async () => {
+      const deviceId = deviceIdFor(115);
+      await attest(deviceId, manufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 110));
+
+      await assertFails(refresh(deviceId, otherKey, 111), "DeviceKeyMismatch");
+    });
+
+    it("Rejects a quote not signed by the device key", async () => {
+      const deviceId = deviceIdFor(116);
+      await attest(deviceId, manufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 110));
+
+      await assertFails(refresh(deviceId, deviceKey, 111, deviceOwner, otherKey), "InvalidQuoteSignature");
+    });
+  });
+
+  describe("attester registration", () => {
+    const squatter = Keypair.generate();
+
+    before(async () => {
+      await airdrop(squatter);
+    });
+
+    it("Stops an unregistered wallet from claiming a device's attestation record", async () => {
+      const deviceId = deviceIdFor(120);
+
+      await assertFails(attest(deviceId, manufacturerId, squatter), "AccountNotInitialized");
+
+      // Nor can it borrow a registered attester's account
+      const attestationQuote = quote();
+      await assertFails(
+        program.methods
+          .createAttestation(Array.from(deviceId), Array.from(manufacturerId), squatter.publicKey, attestationQuote, new Array(1024).fill(6))
+          .accounts({
+            attester: squatter.publicKey,
+            attestationRecord: attestationPda(deviceId),
+            deviceSeries: deviceSeriesPda(deviceId),
+            manufacturerAccount,
+            attesterAccount: findPda([Buffer.from("attester"), manufacturerId, attester.publicKey.toBuffer()], program.programId),
+            attestationAuthority,
+            measurementDeprecation: measurementDeprecationPda(attestationQuote.measurements),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([squatter])
+          .rpc(),
+        "ConstraintSeeds"
+      );
+
+      assert.isNull(await program.account.attestationRecord.fetchNullable(attestationPda(deviceId)));
+      await attest(deviceId);
+    });
+  });
+
+  describe("manufacturer lifecycle", () => {
+    const retiringManufacturer = Keypair.generate();
+    const retiringManufacturerId = new Uint8Array(32).fill(37, 0, 32);
+    const graceManufacturer = Keypair.generate();
+    const graceManufacturerId = new Uint8Array(32).fill(38, 0, 32);
+
+    // Pass and execute a governance action on a manufacturer account
+    const updateManufacturer = async (instruction: "deactivateManufacturer" | "removeManufacturer", id: Uint8Array) => {
+      const proposal = await passProposal({ [instruction]: { manufacturerId: Array.from(id) } });
+      await program.methods[instruction](Array.from(id))
+        .accounts({
+          executor: governanceExecutor.publicKey,
+          manufacturerAccount: findPda([Buffer.from("manufacturer"), id], program.programId),
+          attestationAuthority,
+          proposal,
+        })
+        .signers([governanceExecutor])
+        .rpc();
+    };
+
+    before(async () => {
+      await airdrop(retiringManufacturer, graceManufacturer);
+      await addManufacturer(retiringManufacturerId, retiringManufacturer.publicKey);
+      await addAttester(retiringManufacturerId, retiringManufacturer, attester.publicKey);
+      await addManufacturer(graceManufacturerId, graceManufacturer.publicKey);
+      await addAttester(graceManufacturerId, graceManufacturer, attester.publicKey);
+      await setPolicy(graceManufacturerId, graceManufacturer, {
+        validityPeriod: new anchor.BN(3),
+        gracePeriod: new anchor.BN(3),
+      });
+    });
+
+    it("Refuses to remove an active manufacturer", async () => {
+      await assertFails(updateManufacturer("removeManufacturer", retiringManufacturerId), "ManufacturerStillActive");
+    });
+
+    it("Stops trusting a deactivated manufacturer's attestations", async () => {
+      const deviceId = deviceIdFor(130);
+      await attest(deviceId, retiringManufacturerId);
+      assert.deepEqual((await verify(deviceId)).validity, { valid: {} });
+
+      await updateManufacturer("deactivateManufacturer", retiringManufacturerId);
+      await assertFails(verify(deviceId), "UntrustedManufacturer");
+      await assertFails(attest(deviceIdFor(131), retiringManufacturerId), "UntrustedManufacturer");
+      await assertFails(updateManufacturer("deactivateManufacturer", retiringManufacturerId), "UntrustedManufacturer");
+    });
+
+    it("Removes a deactivated manufacturer", async () => {
+      const { totalManufacturers } = await program.account.attestationAuthority.fetch(attestationAuthority);
+      await updateManufacturer("removeManufacturer", retiringManufacturerId);
+
+      assert.isNull(
+        await program.account.manufacturerAccount.fetchNullable(
+          findPda([Buffer.from("manufacturer"), retiringManufacturerId], program.programId)
+        )
+      );
+      const authority = await program.account.attestationAuthority.fetch(attestationAuthority);
+      assert.equal(authority.totalManufacturers.toNumber(), totalManufacturers.toNumber() - 1);
+    });
+
+    it("Does not revive a removed manufacturer's attestations when it is added again", async () => {
+      await addManufacturer(retiringManufacturerId, retiringManufacturer.publicKey);
+      const manufacturerState = await program.account.manufacturerAccount.fetch(
+        findPda([Buffer.from("manufacturer"), retiringManufacturerId], program.programId)
+      );
+      const oldRecord = await program.account.attestationRecord.fetch(attestationPda(deviceIdFor(130)));
+      assert.isAbove(manufacturerState.generation.toNumber(), oldRecord.manufacturerGeneration.toNumber());
+
+      await assertFails(verify(deviceIdFor(130)), "StaleManufacturer");
+
+      // The attester's registration outlived the removal, so new attestations go through
+      const deviceId = deviceIdFor(134);
+      await attest(deviceId, retiringManufacturerId);
+      assert.deepEqual((await verify(deviceId)).validity, { valid: {} });
+    });
+
+    it("Registers more than ten manufacturers and attests against the eleventh", async () => {
+      const { totalManufacturers } = await program.account.attestationAuthority.fetch(attestationAuthority);
+      const manufacturers = Array.from({ length: 11 }, (_, i) => ({
+        key: Keypair.generate(),
+        id: new Uint8Array(32).fill(50 + i, 0, 32),
+      }));
+      for (const { key, id } of manufacturers) {
+        await addManufacturer(id, key.publicKey);
+      }
+      const authority = await program.account.attestationAuthority.fetch(attestationAuthority);
+      assert.equal(authority.totalManufacturers.toNumber(), totalManufacturers.toNumber() + 11);
+
+      const eleventh = manufacturers[10];
+      await airdrop(eleventh.key);
+      await addAttester(eleventh.id, eleventh.key, attester.publicKey);
+      const deviceId = deviceIdFor(133);
+      await attest(deviceId, eleventh.id);
+      assert.deepEqual((await verify(deviceId)).validity, { valid: {} });
+      assert.deepEqual((await verify(deviceId)).manufacturerId, Array.from(eleventh.id));
+    });
+
+    it("Reports NeedsRefresh during the grace period and fails after it", async () => {
+      const deviceId = deviceIdFor(132);
+      await attest(deviceId, graceManufacturerId);
+      assert.deepEqual((await verify(deviceId)).validity, { valid: {} });
+
+      await sleep(4500);
+      assert.deepEqual((await verify(deviceId)).validity, { needsRefresh: {} });
+
+      await sleep(3000);
+      await assertFails(verify(deviceId), "AttestationExpired");
+    });
+  });
+
+  describe("expiry and co-signing", () => {
+    // Manufacturer whose attestations expire after a second, with no grace period
+    const expiringManufacturer = Keypair.generate();
+    const expiringManufacturerId = new Uint8Array(32).fill(39, 0, 32);
+    const deviceKey = Keypair.generate();
+
+    const expire = (deviceId: Uint8Array, id: Uint8Array = expiringManufacturerId) =>
+      program.methods
+        .expireAttestation(Array.from(deviceId))
+        .accounts({
+          attestationRecord: attestationPda(deviceId),
+          manufacturerAccount: findPda([Buffer.from("manufacturer"), id], program.programId),
+        })
+        .rpc();
+
+    const confirm = (deviceId: Uint8Array, signer: Keypair = cosignManufacturer) =>
+      program.methods
+        .confirmAttestation(Array.from(deviceId))
+        .accounts({
+          manufacturer: signer.publicKey,
+          attestationRecord: attestationPda(deviceId),
+          manufacturerAccount: cosignManufacturerAccount,
+          attestationAuthority,
+        })
+        .signers([signer])
+        .rpc();
+
+    before(async () => {
+      await airdrop(expiringManufacturer);
+      await addManufacturer(expiringManufacturerId, expiringManufacturer.publicKey);
+      await addAttester(expiringManufacturerId, expiringManufacturer, attester.publicKey);
+      await setPolicy(expiringManufacturerId, expiringManufacturer, { validityPeriod: new anchor.BN(1) });
+    });
+
+    it("Lets anyone expire a lapsed attestation, and a refresh revives it", async () => {
+      const deviceId = deviceIdFor(140);
+      await attest(deviceId, expiringManufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 140));
+      await assertFails(expire(deviceId), "AttestationNotExpired");
+
+      await sleep(2000);
+      await expire(deviceId);
+      let record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.status, { expired: {} });
+      await assertFails(verify(deviceId), "InvalidAttestation");
+      await assertFails(expire(deviceId), "InvalidAttestation");
+
+      await refresh(deviceId, deviceKey, 141);
+      record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.status, { valid: {} });
+    });
+
+    it("Holds attestations pending until the manufacturer co-signs", async () => {
+      const deviceId = deviceIdFor(142);
+      await attest(deviceId, cosignManufacturerId);
+
+      let record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.status, { pending: {} });
+      assert.isFalse(record.cosigned);
+      await assertFails(verify(deviceId), "InvalidAttestation");
+
+      await assertFails(confirm(deviceId, attester), "UnauthorizedManufacturer");
+      await confirm(deviceId);
+
+      record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.status, { valid: {} });
+      assert.isTrue(record.cosigned);
+      assert.deepEqual((await verify(deviceId)).validity, { valid: {} });
+      await assertFails(confirm(deviceId), "AttestationNotPending");
+    });
+  });
+
+  describe("attestation history", () => {
+    const deviceKey = Keypair.generate();
+
+    const historyEntryPda = (deviceId: Uint8Array, series: number, sequence: number) =>
+      findPda([Buffer.from("attestation_history"), deviceId, u32Seed(series), u32Seed(sequence)], program.programId);
+
+    it("Keeps every replaced quote in order", async () => {
+      const deviceId = deviceIdFor(150);
+      const originalQuote = quote(deviceKey.publicKey.toBytes(), 150);
+      await attest(deviceId, manufacturerId, attester, originalQuote);
+      const created = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+
+      await refresh(deviceId, deviceKey, 151);
+      const refreshed = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      await refresh(deviceId, deviceKey, 152);
+
+      const first = await program.account.attestationHistoryEntry.fetch(historyEntryPda(deviceId, created.series, 0));
+      assert.equal(first.sequence, 0);
+      assert.deepEqual(first.quoteHash, Array.from(attestationQuoteHash(deviceId, originalQuote)));
+      assert.deepEqual(first.measurementSetId, created.measurementSetId);
+      assert.deepEqual(Array.from(first.measurements[0]), new Array(32).fill(150));
+      assert.deepEqual(first.manufacturerKey, Array.from(manufacturer.publicKey.toBytes()));
+      assert.equal(first.validFrom.toNumber(), created.attestedAt.toNumber());
+      assert.equal(first.replacedAt.toNumber(), refreshed.attestedAt.toNumber());
+
+      const second = await program.account.attestationHistoryEntry.fetch(historyEntryPda(deviceId, created.series, 1));
+      assert.equal(second.sequence, 1);
+      assert.deepEqual(Array.from(second.measurements[0]), new Array(32).fill(151));
+      assert.equal(second.validFrom.toNumber(), refreshed.attestedAt.toNumber());
+
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.equal(record.historyLength, 2);
+    });
+
+    it("Starts a new series for a re-created record and keeps the old one", async () => {
+      const deviceId = deviceIdFor(153);
+      await attest(deviceId, manufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 150));
+      const { series } = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      await refresh(deviceId, deviceKey, 151);
+
+      const authorityBefore = await program.account.attestationAuthority.fetch(attestationAuthority);
+      const manufacturerBefore = await program.account.manufacturerAccount.fetch(manufacturerAccount);
+
+      await revoke(deviceId, { userRequested: {} });
+      const proposal = await passProposal({ closeAttestation: { deviceId: Array.from(deviceId) } });
+      await program.methods
+        .closeAttestation(Array.from(deviceId))
+        .accounts({
+          executor: governanceExecutor.publicKey,
+          attestationRecord: attestationPda(deviceId),
+          manufacturerAccount,
+          attestationAuthority,
+          proposal,
+        })
+        .signers([governanceExecutor])
+        .rpc();
+
+      // Closing the record takes it out of both counts
+      const authorityAfter = await program.account.attestationAuthority.fetch(attestationAuthority);
+      const manufacturerAfter = await program.account.manufacturerAccount.fetch(manufacturerAccount);
+      assert.equal(authorityAfter.totalAttestations.toNumber(), authorityBefore.totalAttestations.toNumber() - 1);
+      assert.equal(manufacturerAfter.devicesAttested.toNumber(), manufacturerBefore.devicesAttested.toNumber() - 1);
+
+      // Re-created straight away, even within the same second, it gets the next series
+      await attest(deviceId, manufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 152));
+      const recreated = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.equal(recreated.historyLength, 0);
+      assert.equal(recreated.series, series + 1);
+      assert.equal((await program.account.deviceSeries.fetch(deviceSeriesPda(deviceId))).recordsCreated, series + 2);
+
+      await refresh(deviceId, deviceKey, 153);
+      const previous = await program.account.attestationHistoryEntry.fetch(historyEntryPda(deviceId, series, 0));
+      const current = await program.account.attestationHistoryEntry.fetch(historyEntryPda(deviceId, recreated.series, 0));
+      assert.deepEqual(Array.from(previous.measurements[0]), new Array(32).fill(150));
+      assert.deepEqual(Array.from(current.measurements[0]), new Array(32).fill(152));
+    });
+  });
+
+  describe("verify_attestation", () => {
+    const summaryManufacturer = Keypair.generate();
+    const summaryManufacturerId = new Uint8Array(32).fill(41, 0, 32);
+    const deviceKey = Keypair.generate();
+
+    // Any parseable X.509 certificate will do; this one has serial number 1
+    const certificate = readFileSync("programs/shift-attestation/testdata/android_tee_ed25519_v3.der");
+    const deviceCertificate = [...certificate, ...new Array(1024 - certificate.length).fill(0)];
+    const serialHash = Array.from(Buffer.from("4bf5122f344554c53bde2ebb8cd2b7e3d1600ad631c385a5d7cce23c7785459a", "hex"));
+
+    before(async () => {
+      await airdrop(summaryManufacturer);
+      await addManufacturer(summaryManufacturerId, summaryManufacturer.publicKey);
+      await addAttester(summaryManufacturerId, summaryManufacturer, attester.publicKey);
+    });
+
+    it("Summarizes what a verified attestation vouches for", async () => {
+      const deviceId = deviceIdFor(162);
+      await attest(deviceId, summaryManufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 162), deviceCertificate);
+      const record = await program.account.attestationRecord.fetch(attestationPda(deviceId));
+      assert.deepEqual(record.certificateSerialHash, serialHash);
+
+      const summary = await verify(deviceId);
+      assert.deepEqual(summary.deviceId, Array.from(deviceId));
+      assert.deepEqual(summary.deviceKey, Array.from(deviceKey.publicKey.toBytes()));
+      assert.deepEqual(summary.deviceKeyAlgorithm, { ed25519: {} });
+      assert.deepEqual(summary.manufacturerId, Array.from(summaryManufacturerId));
+      assert.deepEqual(summary.hardwareType, { shiftDevice: {} });
+      assert.deepEqual(summary.securityLevel, { secureElement: {} });
+      assert.isNull(summary.verifiedBootState);
+      assert.deepEqual(summary.measurementSetId, Array.from(measurementSetIdOf([new Array(32).fill(162)])));
+      assert.equal(summary.expiresAt.toNumber(), record.expiresAt.toNumber());
+      assert.deepEqual(summary.validity, { valid: {} });
+    });
+  });
+
+  describe("certificate revocation lists", () => {
+    const crlManufacturer = Keypair.generate();
+    const crlManufacturerId = new Uint8Array(32).fill(40, 0, 32);
+    const deviceKey = Keypair.generate();
+
+    // Any parseable X.509 certificate will do; this one has serial number 1
+    const certificate = readFileSync("programs/shift-attestation/testdata/android_tee_ed25519_v3.der");
+    const deviceCertificate = [...certificate, ...new Array(1024 - certificate.length).fill(0)];
+    const serialHash = Array.from(Buffer.from("4bf5122f344554c53bde2ebb8cd2b7e3d1600ad631c385a5d7cce23c7785459a", "hex"));
+    const bucket = serialHash[0];
+    const crlBucket = findPda([Buffer.from("crl"), crlManufacturerId, Buffer.from([bucket])], program.programId);
+
+    const addCrlEntries = (crlNumber: number, serialHashes: number[][], signer: Keypair = crlManufacturer) =>
+      program.methods
+        .addCrlEntries(Array.from(crlManufacturerId), bucket, new anchor.BN(crlNumber), serialHashes)
+        .accounts({
+          manufacturer: signer.publicKey,
+          manufacturerAccount: findPda([Buffer.from("manufacturer"), crlManufacturerId], program.programId),
+          crlBucket,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([signer])
+        .rpc();
+
+    before(async () => {
+      await airdrop(crlManufacturer);
+      await addManufacturer(crlManufacturerId, crlManufacturer.publicKey);
+      await addAttester(crlManufacturerId, crlManufacturer, attester.publicKey);
+      await program.methods
+        .initializeCrlBucket(Array.from(crlManufacturerId), bucket)
+        .accounts({
+          manufacturer: crlManufacturer.publicKey,
+          manufacturerAccount: findPda([Buffer.from("manufacturer"), crlManufacturerId], program.programId),
+          crlBucket,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([crlManufacturer])
+        .rpc();
+    });
+
+    it("Only takes CRL updates from the manufacturer, in order and in the right bucket", async () => {
+      await assertFails(addCrlEntries(1, [serialHash], attester), "UnauthorizedManufacturer");
+      await assertFails(addCrlEntries(1, [new Array(32).fill(bucket + 1)]), "CrlBucketMismatch");
+
+      await addCrlEntries(1, [new Array(32).fill(bucket)]);
+      await assertFails(addCrlEntries(1, [serialHash]), "StaleCrl");
+    });
+
+    it("Stops verifying attestations whose certificate serial has been revoked", async () => {
+      await attest(deviceIdFor(160), crlManufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 160), deviceCertificate);
+      const deviceId = deviceIdFor(161);
+      await attest(deviceId, crlManufacturerId, attester, quote(deviceKey.publicKey.toBytes(), 160));
+      assert.isNull((await program.account.attestationRecord.fetch(attestationPda(deviceId))).certificateSerialHash);
+
+      await addCrlEntries(2, [serialHash]);
+      const crl = await program.account.crlBucket.fetch(crlBucket);
+      assert.equal(crl.crlNumber.toNumber(), 2);
+      assert.equal(crl.serialHashes.length, 2);
+
+      await assertFails(verify(deviceIdFor(160)), "CertificateRevoked");
+      // Records without a parseable certificate are unaffected
+      assert.deepEqual((await verify(deviceId)).validity, { valid: {} });
+    });
+  });
+});
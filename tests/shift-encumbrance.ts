@@ -0,0 +1,893 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { ShiftEncumbrance } from "../target/types/shift_encumbrance";
+import { ShiftAttestation } from "../target/types/shift_attestation";
+import { assert } from "chai";
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  Ed25519Program,
+  SYSVAR_INSTRUCTIONS_PUBKEY
+} from "@solana/web3.js";
+import {
+  computeKeyMerkleRoot,
+  computeMerkleRoot,
+  getKeyMerkleProof,
+  getMerkleProof,
+  keyLeafHash,
+  keyCommitmentLeafHash,
+  destructionMessage,
+  destructionCommitment,
+  destructionUseTag,
+  keyBatchMessage,
+  wotsPublicKey,
+  wotsSign,
+} from "../sdk/utils";
+import {
+  findPda,
+  airdrop,
+  attestationAuthorityPda,
+  measurementDeprecationPda,
+  deviceSeriesPda,
+  setupGovernance,
+  executeGovernanceUpdate,
+  addManufacturer,
+  addAttester,
+  verifyAttestationAccounts,
+  deprecateMeasurementSet,
+} from "./fixtures";
+
+describe("shift-encumbrance", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+
+  const program = anchor.workspace.ShiftEncumbrance as Program<ShiftEncumbrance>;
+  const attestationProgram = anchor.workspace.ShiftAttestation as Program<ShiftAttestation>;
+  const provider = anchor.getProvider();
+
+  // Test accounts
+  let encumbranceAuthority: PublicKey;
+  let keyPool: PublicKey;
+  let attestationAuthority: PublicKey;
+  let manufacturerAccount: PublicKey;
+  let attestationRecord: PublicKey;
+
+  // Test keypairs
+  const authority = Keypair.generate();
+  const manufacturer = Keypair.generate();
+  const attester = Keypair.generate();
+  const deviceOwner = Keypair.generate();
+  const deviceKey = Keypair.generate(); // Key the device attests to
+  const impostorKey = Keypair.generate();
+  const counterparty = Keypair.generate();
+
+  // Test data
+  const deviceId = new Uint8Array(32).fill(9, 0, 32);
+  const manufacturerId = new Uint8Array(32).fill(10, 0, 32);
+  const poolKeypairs = [1, 2, 3, 4, 5, 6].map(() => Keypair.generate());
+  const poolKeys = poolKeypairs.map((keypair) => keypair.publicKey.toBytes());
+  // Key 1 is proven destroyed by revealing the opening of a commitment fixed in its leaf
+  const commitmentReveal = new Uint8Array(32).fill(11, 0, 32);
+  const poolLeaves = poolKeys.map((key, i) =>
+    i === 1 ? keyCommitmentLeafHash(key, destructionCommitment(key, commitmentReveal)) : keyLeafHash(key)
+  );
+
+  const u32le = (value: number) => Buffer.from(new anchor.BN(value).toArray("le", 4));
+
+  const transactionHash = (seed: number) => new Uint8Array(32).fill(seed, 0, 32);
+
+  // Accounts encumbrance instructions pass along to verify_attestation for `id`
+  const attestationAccounts = async (id: Uint8Array) => ({
+    ...(await verifyAttestationAccounts(id)),
+    attestationProgram: attestationProgram.programId,
+  });
+
+  const noAttestationAccounts = {
+    attestationRecord: null,
+    manufacturerAccount: null,
+    measurementDeprecation: null,
+    crlBucket: null,
+    attestationProgram: null,
+  };
+
+  // Submit encumber_key for pool key `keyIndex` with the given proof
+  const encumber = async (
+    keyIndex: number,
+    destructionProof: any,
+    txHash: Uint8Array,
+    preInstructions: anchor.web3.TransactionInstruction[] = [],
+    withAttestation = false
+  ) =>
+    program.methods
+      .encumberKey(
+        Array.from(deviceId),
+        keyIndex,
+        Array.from(poolKeys[keyIndex]),
+        getMerkleProof(poolLeaves, keyIndex).map((node) => Array.from(node)),
+        destructionProof,
+        Array.from(txHash),
+        counterparty.publicKey
+      )
+      .accounts({
+        deviceOwner: deviceOwner.publicKey,
+        keyPool,
+        keyBitmap: findPda([Buffer.from("key_bitmap"), deviceId, u32le(0)], program.programId),
+        encumbranceRecord: findPda(
+          [Buffer.from("encumbrance"), deviceId, u32le(keyIndex)],
+          program.programId
+        ),
+        keyNullifier: findPda([Buffer.from("key_nullifier"), deviceId, poolKeys[keyIndex]], program.programId),
+        encumbranceAuthority,
+        ...(withAttestation ? await attestationAccounts(deviceId) : noAttestationAccounts),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions(preInstructions)
+      .signers([deviceOwner])
+      .rpc();
+
+  const proof = (proofType: any, fields: Partial<Record<string, any>> = {}) => ({
+    proofType,
+    proofData: new Array(256).fill(0),
+    timestamp: new anchor.BN(Math.floor(Date.now() / 1000)),
+    nonce: new Array(32).fill(7),
+    hardwareSignature: new Array(64).fill(0),
+    ...fields,
+  });
+
+  // HardwareAttestation proof that `signer` destroyed `publicKey` for `txHash`, and the
+  // Ed25519 program instruction checking its signature
+  const hardwareProof = (publicKey: Uint8Array, txHash: Uint8Array, signer: Keypair = deviceKey) => {
+    const nonce = new Uint8Array(32).fill(7);
+    const signatureIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: signer.secretKey,
+      message: destructionMessage(publicKey, txHash, nonce),
+    });
+    const signature = signatureIx.data.subarray(16 + 32, 16 + 32 + 64);
+    return {
+      destructionProof: proof({ hardwareAttestation: {} }, { hardwareSignature: Array.from(signature) }),
+      signatureIx,
+    };
+  };
+
+  const assertRejected = async (attempt: Promise<string>, expectedError = "InvalidDestructionProof") => {
+    try {
+      await attempt;
+      assert.fail(`Should have failed with ${expectedError}`);
+    } catch (error) {
+      assert.ok(error.message.includes(expectedError), error.message);
+    }
+  };
+
+  // Attest `id` under the test manufacturer with `deviceKey` as its device key
+  const attestDevice = (id: Uint8Array, measurements: number[][] = [new Array(32).fill(5)]) =>
+    attestationProgram.methods
+      .createAttestation(
+        Array.from(id),
+        Array.from(manufacturerId),
+        deviceOwner.publicKey,
+        {
+          version: 1,
+          signature: new Array(64).fill(2),
+          publicKey: Array.from(deviceKey.publicKey.toBytes()),
+          nonce: new Array(32).fill(4),
+          timestamp: new anchor.BN(Math.floor(Date.now() / 1000)),
+          measurements,
+        },
+        new Array(1024).fill(6)
+      )
+      .accounts({
+        attester: attester.publicKey,
+        attestationRecord: findPda([Buffer.from("attestation"), id], attestationProgram.programId),
+        deviceSeries: deviceSeriesPda(id),
+        manufacturerAccount,
+        attesterAccount: findPda(
+          [Buffer.from("attester"), manufacturerId, attester.publicKey.toBuffer()],
+          attestationProgram.programId
+        ),
+        attestationAuthority,
+        measurementDeprecation: measurementDeprecationPda(measurements),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([attester])
+      .rpc();
+
+  // Submit initialize_key_pool for `id`, with the first batch signed by `signer`
+  const initializeKeyPool = async (
+    id: Uint8Array,
+    merkleRoot: Uint8Array,
+    keyCount: number,
+    options: {
+      keyScheme?: any;
+      wotsPublicSeed?: Uint8Array;
+      keyUsagePolicy?: any;
+      signer?: Keypair;
+      owner?: Keypair;
+    } = {}
+  ) => {
+    const {
+      keyScheme = { standard: {} },
+      wotsPublicSeed = new Uint8Array(32),
+      keyUsagePolicy = { anyOrder: {} },
+      signer = deviceKey,
+      owner = deviceOwner,
+    } = options;
+    const signatureIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: signer.secretKey,
+      message: keyBatchMessage(id, merkleRoot, 0, keyCount),
+    });
+
+    return program.methods
+      .initializeKeyPool(
+        Array.from(id),
+        Array.from(merkleRoot),
+        keyCount,
+        keyScheme,
+        Array.from(wotsPublicSeed),
+        keyUsagePolicy,
+        Array.from(signatureIx.data.subarray(16 + 32, 16 + 32 + 64))
+      )
+      .accounts({
+        owner: owner.publicKey,
+        keyPool: findPda([Buffer.from("key_pool"), id], program.programId),
+        encumbranceAuthority,
+        ...(await attestationAccounts(id)),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([signatureIx])
+      .signers([owner])
+      .rpc();
+  };
+
+  before(async () => {
+    await setupGovernance();
+    await airdrop(authority, manufacturer, attester, deviceOwner);
+
+    // Derive PDAs
+    encumbranceAuthority = findPda([Buffer.from("encumbrance_authority")], program.programId);
+    keyPool = findPda([Buffer.from("key_pool"), deviceId], program.programId);
+    attestationAuthority = attestationAuthorityPda();
+    attestationRecord = findPda([Buffer.from("attestation"), deviceId], attestationProgram.programId);
+
+    // Attest the device
+    manufacturerAccount = await addManufacturer(manufacturerId, manufacturer.publicKey);
+    await addAttester(manufacturerId, manufacturer, attester.publicKey);
+    await attestDevice(deviceId);
+
+    // Let the encumbrance program flag attestations of devices that double-sign
+    await executeGovernanceUpdate({ setFraudReporter: { fraudReporter: encumbranceAuthority } });
+
+    // Set up the encumbrance program and the device's key pool
+    await program.methods
+      .initialize()
+      .accounts({
+        authority: authority.publicKey,
+        encumbranceAuthority,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    await initializeKeyPool(deviceId, computeMerkleRoot(poolLeaves), poolKeys.length);
+  });
+
+  it("Only creates key pools for attested devices, signed by their device key", async () => {
+    const unattestedDeviceId = new Uint8Array(32).fill(12, 0, 32);
+    await assertRejected(
+      initializeKeyPool(unattestedDeviceId, computeKeyMerkleRoot(poolKeys), poolKeys.length),
+      "AccountNotInitialized"
+    );
+
+    const attestedDeviceId = new Uint8Array(32).fill(13, 0, 32);
+    await attestDevice(attestedDeviceId);
+    await assertRejected(
+      initializeKeyPool(attestedDeviceId, computeKeyMerkleRoot(poolKeys), poolKeys.length, { signer: impostorKey }),
+      "InvalidBatchSignature"
+    );
+    await assertRejected(
+      initializeKeyPool(attestedDeviceId, computeKeyMerkleRoot(poolKeys), poolKeys.length, { owner: attester }),
+      "UnauthorizedOwner"
+    );
+  });
+
+  it("Only takes new keys from attestations the attestation program still verifies", async () => {
+    const measurements = [new Array(32).fill(14)];
+    const pooledDeviceId = new Uint8Array(32).fill(14, 0, 32);
+    const newDeviceId = new Uint8Array(32).fill(15, 0, 32);
+    await attestDevice(pooledDeviceId, measurements);
+    await attestDevice(newDeviceId, measurements);
+    await initializeKeyPool(pooledDeviceId, computeKeyMerkleRoot(poolKeys), poolKeys.length);
+
+    // Still marked Valid, but no longer trusted once its firmware is deprecated
+    await deprecateMeasurementSet(measurements);
+    await assertRejected(
+      initializeKeyPool(newDeviceId, computeKeyMerkleRoot(poolKeys), poolKeys.length),
+      "MeasurementSetDeprecated"
+    );
+    await assertRejected(
+      replenish([new Uint8Array(32).fill(16, 0, 32)], deviceKey, pooledDeviceId),
+      "MeasurementSetDeprecated"
+    );
+  });
+
+  it("Accepts a hardware attestation proof signed by the attested device key", async () => {
+    const txHash = transactionHash(20);
+    const { destructionProof, signatureIx } = hardwareProof(poolKeys[0], txHash);
+
+    await encumber(0, destructionProof, txHash, [signatureIx], true);
+
+    const record = await program.account.encumbranceRecord.fetch(
+      findPda([Buffer.from("encumbrance"), deviceId, u32le(0)], program.programId)
+    );
+    assert.deepEqual(record.status, { encumbered: {} });
+    assert.deepEqual(Array.from(record.transactionHash), Array.from(txHash));
+  });
+
+  it("Rejects a hardware attestation proof signed by another key", async () => {
+    const txHash = transactionHash(21);
+    const { destructionProof, signatureIx } = hardwareProof(poolKeys[1], txHash, impostorKey);
+
+    await assertRejected(encumber(1, destructionProof, txHash, [signatureIx], true));
+  });
+
+  // CryptographicCommitment proof carrying `reveal` and its use tag for `txHash`
+  const commitmentProof = (reveal: Uint8Array, txHash: Uint8Array) => {
+    const proofData = new Array(256).fill(0);
+    proofData.splice(0, 32, ...reveal);
+    proofData.splice(32, 32, ...destructionUseTag(reveal, txHash));
+    return proof({ cryptographicCommitment: {} }, { proofData });
+  };
+
+  it("Rejects a reveal that does not open the commitment in the key's leaf", async () => {
+    const wrongReveal = new Uint8Array(32).fill(12);
+    await assertRejected(encumber(1, commitmentProof(wrongReveal, transactionHash(22)), transactionHash(22)));
+  });
+
+  it("Rejects a reveal tagged for a different transaction", async () => {
+    await assertRejected(encumber(1, commitmentProof(commitmentReveal, transactionHash(21)), transactionHash(22)));
+  });
+
+  it("Accepts a reveal that opens the commitment in the key's leaf", async () => {
+    await encumber(1, commitmentProof(commitmentReveal, transactionHash(22)), transactionHash(22));
+
+    const record = await program.account.encumbranceRecord.fetch(
+      findPda([Buffer.from("encumbrance"), deviceId, u32le(1)], program.programId)
+    );
+    assert.deepEqual(record.destructionProof.proofType, { cryptographicCommitment: {} });
+  });
+
+  it("Rejects encumbering the same key twice", async () => {
+    await assertRejected(
+      encumber(1, commitmentProof(commitmentReveal, transactionHash(25)), transactionHash(25)),
+      "KeyAlreadyEncumbered"
+    );
+  });
+
+  it("Rejects a commitment proof for a key committed without one", async () => {
+    await assertRejected(encumber(2, commitmentProof(commitmentReveal, transactionHash(23)), transactionHash(23)));
+  });
+
+  it("Rejects zero-knowledge proofs, which cannot be verified on-chain", async () => {
+    await assertRejected(
+      encumber(
+        2,
+        proof({ zeroKnowledge: {} }, {
+          proofData: new Array(256).fill(12),
+          hardwareSignature: new Array(64).fill(13),
+        }),
+        transactionHash(24)
+      ),
+      "UnsupportedProofType"
+    );
+  });
+
+  // Submit replenish_key_pool for `batchKeys` starting at `firstIndex` of `id`'s pool, signed by `signer`
+  const replenish = async (batchKeys: Uint8Array[], signer: Keypair, id = deviceId, firstIndex = poolKeys.length) => {
+    const merkleRoot = computeKeyMerkleRoot(batchKeys);
+    const signatureIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: signer.secretKey,
+      message: keyBatchMessage(id, merkleRoot, firstIndex, batchKeys.length),
+    });
+    const signature = signatureIx.data.subarray(16 + 32, 16 + 32 + 64);
+
+    return program.methods
+      .replenishKeyPool(Array.from(id), Array.from(merkleRoot), batchKeys.length, Array.from(signature))
+      .accounts({
+        owner: deviceOwner.publicKey,
+        keyPool: findPda([Buffer.from("key_pool"), id], program.programId),
+        ...(await attestationAccounts(id)),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([signatureIx])
+      .signers([deviceOwner])
+      .rpc();
+  };
+
+  it("Rejects a key batch not signed by the attested device key", async () => {
+    const batchKeys = [30, 31].map((i) => new Uint8Array(32).fill(i, 0, 32));
+    try {
+      await replenish(batchKeys, impostorKey);
+      assert.fail("Should have failed with an invalid batch signature");
+    } catch (error) {
+      assert.ok(error.message.includes("InvalidBatchSignature"), error.message);
+    }
+  });
+
+  it("Adds a key batch signed by the attested device key", async () => {
+    const batchKeys = [40, 41, 42, 43].map((i) => new Uint8Array(32).fill(i, 0, 32));
+    await replenish(batchKeys, deviceKey);
+
+    const keyPoolData = await program.account.keyPool.fetch(keyPool);
+    assert.equal(keyPoolData.keyBatches.length, 2);
+    assert.equal(keyPoolData.keyBatches[1].firstIndex, poolKeys.length);
+    assert.equal(keyPoolData.totalKeys, poolKeys.length + batchKeys.length);
+  });
+
+  const encumbranceRecordFor = (keyIndex: number) =>
+    findPda([Buffer.from("encumbrance"), deviceId, u32le(keyIndex)], program.programId);
+
+  const markVerified = (keyIndex: number, signer: Keypair) =>
+    program.methods
+      .markEncumbranceVerified(Array.from(deviceId), keyIndex)
+      .accounts({
+        counterparty: signer.publicKey,
+        encumbranceRecord: encumbranceRecordFor(keyIndex),
+      })
+      .signers([signer])
+      .rpc();
+
+  it("Rejects the device owner verifying its own encumbrance", async () => {
+    try {
+      await markVerified(1, deviceOwner);
+      assert.fail("Should have failed with an unauthorized counterparty");
+    } catch (error) {
+      assert.ok(error.message.includes("UnauthorizedCounterparty"), error.message);
+    }
+  });
+
+  it("Rejects verification by anyone but the encumbrance's counterparty", async () => {
+    await assertRejected(markVerified(1, impostorKey), "UnauthorizedCounterparty");
+  });
+
+  it("Marks an encumbrance verified by the counterparty", async () => {
+    await markVerified(1, counterparty);
+
+    const record = await program.account.encumbranceRecord.fetch(encumbranceRecordFor(1));
+    assert.deepEqual(record.status, { verified: {} });
+    assert.ok(record.counterparty.equals(counterparty.publicKey));
+    assert.ok(record.verifiedBy.equals(counterparty.publicKey));
+  });
+
+  // Submit dispute_encumbrance with pool key `keyIndex` signing both transaction hashes
+  const dispute = (keyIndex: number, transactionHashes: [Uint8Array, Uint8Array]) => {
+    const signatureIxs = transactionHashes.map((message) =>
+      Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: poolKeypairs[keyIndex].secretKey,
+        message,
+      })
+    );
+    const signatures = signatureIxs.map((ix) => Array.from(ix.data.subarray(16 + 32, 16 + 32 + 64)));
+
+    return program.methods
+      .disputeEncumbrance(Array.from(deviceId), keyIndex, {
+        ed25519: {
+          transactionHashes: transactionHashes.map((hash) => Array.from(hash)),
+          signatures,
+        },
+      })
+      .accounts({
+        disputer: counterparty.publicKey,
+        keyPool,
+        encumbranceRecord: encumbranceRecordFor(keyIndex),
+        encumbranceAuthority,
+        attestationAuthority,
+        attestationRecord,
+        attestationProgram: attestationProgram.programId,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .preInstructions(signatureIxs)
+      .signers([counterparty])
+      .rpc();
+  };
+
+  it("Rejects a dispute whose signatures cover the same transaction", async () => {
+    try {
+      await dispute(0, [transactionHash(20), transactionHash(20)]);
+      assert.fail("Should have failed with an invalid double-sign proof");
+    } catch (error) {
+      assert.ok(error.message.includes("InvalidDoubleSignProof"), error.message);
+    }
+  });
+
+  it("Freezes the pool and flags the attestation on a proven double-sign", async () => {
+    await dispute(0, [transactionHash(20), transactionHash(50)]);
+
+    const record = await program.account.encumbranceRecord.fetch(encumbranceRecordFor(0));
+    assert.deepEqual(record.status, { disputed: {} });
+
+    const keyPoolData = await program.account.keyPool.fetch(keyPool);
+    assert.isTrue(keyPoolData.frozen);
+
+    const attestation = await attestationProgram.account.attestationRecord.fetch(attestationRecord);
+    assert.deepEqual(attestation.status, { flagged: {} });
+
+    await assertRejected(
+      encumber(4, commitmentProof(commitmentReveal, transactionHash(51)), transactionHash(51)),
+      "KeyPoolFrozen"
+    );
+  });
+
+  // Submit submit_double_sign_proof for pool key `keyIndex`, signed by `signer`, locating
+  // the key under `commitment` if given
+  const submitDoubleSign = (keyIndex: number, signer: Keypair, commitment: Uint8Array | null = null) => {
+    const transactionHashes = [transactionHash(60), transactionHash(61)];
+    const signatureIxs = transactionHashes.map((message) =>
+      Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message })
+    );
+    const signatures = signatureIxs.map((ix) => Array.from(ix.data.subarray(16 + 32, 16 + 32 + 64)));
+
+    return program.methods
+      .submitDoubleSignProof(
+        Array.from(deviceId),
+        keyIndex,
+        Array.from(poolKeys[keyIndex]),
+        getMerkleProof(poolLeaves, keyIndex).map((node) => Array.from(node)),
+        commitment && Array.from(commitment),
+        {
+          ed25519: {
+            transactionHashes: transactionHashes.map((hash) => Array.from(hash)),
+            signatures,
+          },
+        }
+      )
+      .accounts({
+        submitter: counterparty.publicKey,
+        keyPool,
+        encumbranceAuthority,
+        attestationAuthority,
+        attestationRecord,
+        attestationProgram: attestationProgram.programId,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .preInstructions(signatureIxs)
+      .signers([counterparty])
+      .rpc();
+  };
+
+  it("Rejects a double-sign proof signed by a key outside the pool", async () => {
+    try {
+      await submitDoubleSign(5, impostorKey);
+      assert.fail("Should have failed with an invalid double-sign proof");
+    } catch (error) {
+      assert.ok(error.message.includes("InvalidDoubleSignProof"), error.message);
+    }
+  });
+
+  it("Flags the attestation on a double-sign by an unused pool key", async () => {
+    await submitDoubleSign(5, poolKeypairs[5]);
+
+    // Revoking the device is left to governance
+    const attestation = await attestationProgram.account.attestationRecord.fetch(attestationRecord);
+    assert.deepEqual(attestation.status, { flagged: {} });
+    assert.isNull(attestation.revocationReason);
+  });
+
+  it("Locates a key with a commitment leaf only by its commitment", async () => {
+    try {
+      await submitDoubleSign(1, poolKeypairs[1]);
+      assert.fail("Should have failed with a key mismatch");
+    } catch (error) {
+      assert.ok(error.message.includes("KeyMismatch"), error.message);
+    }
+
+    await submitDoubleSign(1, poolKeypairs[1], destructionCommitment(poolKeys[1], commitmentReveal));
+    const pool = await program.account.keyPool.fetch(keyPool);
+    assert.isTrue(pool.frozen);
+  });
+
+  describe("sequential key usage", () => {
+    const sequentialDeviceId = new Uint8Array(32).fill(70, 0, 32);
+    const sequentialKeys = [71, 72, 73, 74].map((i) => new Uint8Array(32).fill(i, 0, 32));
+    // A later batch that commits key 73, already used at index 2, again at index 5
+    const replenishedKeys = [75, 73].map((i) => new Uint8Array(32).fill(i, 0, 32));
+    let sequentialPool: PublicKey;
+
+    const sequentialBitmap = findPda([Buffer.from("key_bitmap"), sequentialDeviceId, u32le(0)], program.programId);
+
+    // Sequential pools take no bitmap page unless `withBitmap` is set
+    const encumberSequential = async (keyIndex: number, withBitmap = false) => {
+      const [batchKeys, batchIndex] =
+        keyIndex < sequentialKeys.length
+          ? [sequentialKeys, keyIndex]
+          : [replenishedKeys, keyIndex - sequentialKeys.length];
+      const publicKey = batchKeys[batchIndex];
+      const txHash = transactionHash(80 + keyIndex);
+      const { destructionProof, signatureIx } = hardwareProof(publicKey, txHash);
+
+      return program.methods
+        .encumberKey(
+          Array.from(sequentialDeviceId),
+          keyIndex,
+          Array.from(publicKey),
+          getKeyMerkleProof(batchKeys, batchIndex).map((node) => Array.from(node)),
+          destructionProof,
+          Array.from(txHash),
+          counterparty.publicKey
+        )
+        .accounts({
+          deviceOwner: deviceOwner.publicKey,
+          keyPool: sequentialPool,
+          keyBitmap: withBitmap ? sequentialBitmap : null,
+          encumbranceRecord: findPda(
+            [Buffer.from("encumbrance"), sequentialDeviceId, u32le(keyIndex)],
+            program.programId
+          ),
+          keyNullifier: findPda([Buffer.from("key_nullifier"), sequentialDeviceId, publicKey], program.programId),
+          encumbranceAuthority,
+          ...(await attestationAccounts(sequentialDeviceId)),
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions([signatureIx])
+        .signers([deviceOwner])
+        .rpc();
+    };
+
+    before(async () => {
+      sequentialPool = findPda([Buffer.from("key_pool"), sequentialDeviceId], program.programId);
+      await attestDevice(sequentialDeviceId);
+
+      await initializeKeyPool(sequentialDeviceId, computeKeyMerkleRoot(sequentialKeys), sequentialKeys.length, {
+        keyUsagePolicy: { sequential: {} },
+      });
+    });
+
+    it("Advances the high-water mark past skipped keys", async () => {
+      await encumberSequential(0);
+      await encumberSequential(2);
+
+      const keyPoolData = await program.account.keyPool.fetch(sequentialPool);
+      assert.equal(keyPoolData.nextKeyIndex, 3);
+      assert.equal(keyPoolData.usedKeys, 2);
+      assert.equal(keyPoolData.availableKeys, 1);
+
+      // The high-water mark alone tracks use, so no bitmap page is created
+      assert.isNull(await provider.connection.getAccountInfo(sequentialBitmap));
+    });
+
+    it("Rejects a key below the high-water mark", async () => {
+      try {
+        await encumberSequential(1);
+        assert.fail("Should have failed with an out-of-sequence key");
+      } catch (error) {
+        assert.ok(error.message.includes("KeyOutOfSequence"), error.message);
+      }
+    });
+
+    it("Rejects reusing the last key", async () => {
+      await assertRejected(encumberSequential(2), "KeyOutOfSequence");
+    });
+
+    it("Rejects a bitmap page for a sequential pool", async () => {
+      await assertRejected(encumberSequential(3, true), "KeyUsageAccountsMismatch");
+    });
+
+    it("Rejects a key used in an earlier batch, even above the high-water mark", async () => {
+      await replenish(replenishedKeys, deviceKey, sequentialDeviceId, sequentialKeys.length);
+      await encumberSequential(4);
+      await assertRejected(encumberSequential(5), "KeyAlreadyEncumbered");
+
+      const keyPoolData = await program.account.keyPool.fetch(sequentialPool);
+      assert.equal(keyPoolData.nextKeyIndex, 5);
+    });
+  });
+
+  describe("WOTS+ key pools", () => {
+    const wotsDeviceId = new Uint8Array(32).fill(90, 0, 32);
+    const wotsSecretSeed = new Uint8Array(32).fill(91, 0, 32); // Never leaves the device
+    const wotsPublicSeed = new Uint8Array(32).fill(92, 0, 32);
+    const wotsKeys = [0, 1, 2, 3].map((i) => wotsPublicKey(wotsSecretSeed, wotsPublicSeed, i));
+    let wotsPool: PublicKey;
+
+    // Compute units a confirmed transaction consumed
+    const computeUnits = async (signature: string) => {
+      const transaction = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      return transaction.meta.computeUnitsConsumed;
+    };
+
+    const encumberWots = (keyIndex: number, signature: Uint8Array[], txHash: Uint8Array) =>
+      program.methods
+        .encumberKeyWots(
+          Array.from(wotsDeviceId),
+          keyIndex,
+          Array.from(wotsKeys[keyIndex]),
+          getKeyMerkleProof(wotsKeys, keyIndex).map((node) => Array.from(node)),
+          signature.map((value) => Array.from(value)),
+          Array.from(txHash),
+          counterparty.publicKey
+        )
+        .accounts({
+          deviceOwner: deviceOwner.publicKey,
+          keyPool: wotsPool,
+          keyBitmap: findPda([Buffer.from("key_bitmap"), wotsDeviceId, u32le(0)], program.programId),
+          encumbranceRecord: findPda(
+            [Buffer.from("encumbrance"), wotsDeviceId, u32le(keyIndex)],
+            program.programId
+          ),
+          keyNullifier: findPda([Buffer.from("key_nullifier"), wotsDeviceId, wotsKeys[keyIndex]], program.programId),
+          encumbranceAuthority,
+          ...noAttestationAccounts,
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([deviceOwner])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      wotsPool = findPda([Buffer.from("key_pool"), wotsDeviceId], program.programId);
+      await attestDevice(wotsDeviceId);
+
+      await initializeKeyPool(wotsDeviceId, computeKeyMerkleRoot(wotsKeys), wotsKeys.length, {
+        keyScheme: { wotsPlus: {} },
+        wotsPublicSeed,
+      });
+    });
+
+    it("Encumbers a key with its WOTS+ signature over the transaction, within the default compute budget", async () => {
+      const txHash = transactionHash(100);
+      const signature = await encumberWots(0, wotsSign(wotsSecretSeed, wotsPublicSeed, 0, txHash), txHash);
+
+      const record = await program.account.encumbranceRecord.fetch(
+        findPda([Buffer.from("encumbrance"), wotsDeviceId, u32le(0)], program.programId)
+      );
+      assert.deepEqual(record.destructionProof.proofType, { wotsSignature: {} });
+      assert.isBelow(await computeUnits(signature), 200_000);
+    });
+
+    it("Rejects a WOTS+ signature over a different transaction", async () => {
+      const signature = wotsSign(wotsSecretSeed, wotsPublicSeed, 1, transactionHash(102));
+      await assertRejected(encumberWots(1, signature, transactionHash(103)));
+    });
+
+    // A second signature by key 0, over a hash other than the one it was encumbered with
+    const secondUse = {
+      wots: {
+        transactionHash: Array.from(transactionHash(101)),
+        signature: wotsSign(wotsSecretSeed, wotsPublicSeed, 0, transactionHash(101)).map((value) => Array.from(value)),
+      },
+    };
+    const wotsAttestationRecord = findPda([Buffer.from("attestation"), wotsDeviceId], attestationProgram.programId);
+
+    it("Only takes WOTS+ double-sign evidence against an encumbrance", async () => {
+      await assertRejected(
+        program.methods
+          .submitDoubleSignProof(
+            Array.from(wotsDeviceId),
+            0,
+            Array.from(wotsKeys[0]),
+            getKeyMerkleProof(wotsKeys, 0).map((node) => Array.from(node)),
+            null,
+            secondUse
+          )
+          .accounts({
+            submitter: counterparty.publicKey,
+            keyPool: wotsPool,
+            encumbranceAuthority,
+            attestationAuthority,
+            attestationRecord: wotsAttestationRecord,
+            attestationProgram: attestationProgram.programId,
+            instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .signers([counterparty])
+          .rpc(),
+        "EncumbranceRequired"
+      );
+    });
+
+    it("Only accepts a WOTS+ double-sign once it may flag the attestation, then freezes the pool and flags it", async () => {
+      const disputeWots = () =>
+        program.methods
+          .disputeEncumbrance(Array.from(wotsDeviceId), 0, secondUse)
+          .accounts({
+            disputer: counterparty.publicKey,
+            keyPool: wotsPool,
+            encumbranceRecord: findPda([Buffer.from("encumbrance"), wotsDeviceId, u32le(0)], program.programId),
+            encumbranceAuthority,
+            attestationAuthority,
+            attestationRecord: wotsAttestationRecord,
+            attestationProgram: attestationProgram.programId,
+            instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .signers([counterparty])
+          .rpc({ commitment: "confirmed" });
+
+      // Without the fraud reporter role the dispute fails rather than leave the attestation untouched
+      await executeGovernanceUpdate({ setFraudReporter: { fraudReporter: null } });
+      try {
+        await assertRejected(disputeWots(), "FraudReporterNotSet");
+      } finally {
+        await executeGovernanceUpdate({ setFraudReporter: { fraudReporter: encumbranceAuthority } });
+      }
+      assert.isFalse((await program.account.keyPool.fetch(wotsPool)).frozen);
+
+      assert.isBelow(await computeUnits(await disputeWots()), 200_000);
+      const keyPoolData = await program.account.keyPool.fetch(wotsPool);
+      assert.isTrue(keyPoolData.frozen);
+      const attestation = await attestationProgram.account.attestationRecord.fetch(wotsAttestationRecord);
+      assert.deepEqual(attestation.status, { flagged: {} });
+    });
+  });
+
+  describe("key batches", () => {
+    const batchDeviceId = new Uint8Array(32).fill(110, 0, 32);
+    const initialKeys = [111, 112].map((i) => new Uint8Array(32).fill(i, 0, 32));
+    let batchPool: PublicKey;
+
+    // Add a one-key batch, signed by the attested device key
+    const addBatch = async (key: Uint8Array) => {
+      const { totalKeys } = await program.account.keyPool.fetch(batchPool);
+      const merkleRoot = computeKeyMerkleRoot([key]);
+      const signatureIx = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: deviceKey.secretKey,
+        message: keyBatchMessage(batchDeviceId, merkleRoot, totalKeys, 1),
+      });
+
+      return program.methods
+        .replenishKeyPool(
+          Array.from(batchDeviceId),
+          Array.from(merkleRoot),
+          1,
+          Array.from(signatureIx.data.subarray(16 + 32, 16 + 32 + 64))
+        )
+        .accounts({
+          owner: deviceOwner.publicKey,
+          keyPool: batchPool,
+          ...(await attestationAccounts(batchDeviceId)),
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions([signatureIx])
+        .signers([deviceOwner])
+        .rpc();
+    };
+
+    before(async () => {
+      batchPool = findPda([Buffer.from("key_pool"), batchDeviceId], program.programId);
+      await attestDevice(batchDeviceId);
+
+      await initializeKeyPool(batchDeviceId, computeKeyMerkleRoot(initialKeys), initialKeys.length);
+    });
+
+    it("Grows the pool account by one batch entry per batch", async () => {
+      const sizeBefore = (await provider.connection.getAccountInfo(batchPool))!.data.length;
+      await addBatch(new Uint8Array(32).fill(120, 0, 32));
+
+      const sizeAfter = (await provider.connection.getAccountInfo(batchPool))!.data.length;
+      assert.equal(sizeAfter - sizeBefore, 32 + 4 + 4);
+      const keyPoolData = await program.account.keyPool.fetch(batchPool);
+      assert.equal(keyPoolData.keyBatches.length, 2);
+      assert.equal(keyPoolData.totalKeys, initialKeys.length + 1);
+    });
+
+    it("Rejects a batch that is already in the pool", async () => {
+      await assertRejected(addBatch(new Uint8Array(32).fill(120, 0, 32)), "DuplicateKeyBatch");
+    });
+
+    it("Stops adding batches at the pool's limit", async () => {
+      const MAX_KEY_BATCHES = 64;
+      for (let batch = 2; batch < MAX_KEY_BATCHES; batch++) {
+        await addBatch(new Uint8Array(32).fill(120 + batch, 0, 32));
+      }
+      const keyPoolData = await program.account.keyPool.fetch(batchPool);
+      assert.equal(keyPoolData.keyBatches.length, MAX_KEY_BATCHES);
+
+      await assertRejected(addBatch(new Uint8Array(32).fill(200, 0, 32)), "KeyPoolFull");
+    });
+  });
+});
@@ -0,0 +1,70 @@
+import { assert } from "chai";
+import { createHash } from "crypto";
+import {
+  destructionMessage,
+  keyBatchMessage,
+  destructionCommitment,
+  destructionUseTag,
+  wotsPublicKey,
+  wotsSign,
+  wotsPublicKeyFromSignature,
+} from "../sdk/utils";
+
+// Known-answer vectors from crates/shift-key-proofs, so the SDK builds the same
+// messages and keys as device firmware and the on-chain programs
+describe("shift-key-proofs vectors", () => {
+  const publicKey = new Uint8Array(32).fill(0x01);
+  const transactionHash = new Uint8Array(32).fill(0x02);
+  const nonce = new Uint8Array(32).fill(0x03);
+  const deviceId = new Uint8Array(32).fill(0x04);
+  const merkleRoot = new Uint8Array(32).fill(0x05);
+  const reveal = new Uint8Array(32).fill(0x06);
+
+  const hex = (bytes: Uint8Array) => Buffer.from(bytes).toString("hex");
+
+  it("destructionMessage", () => {
+    assert.equal(
+      hex(destructionMessage(publicKey, transactionHash, nonce)),
+      "4efca7d86d1f6ff8ac12f9ab17854230d3c1c5b70e5354c75790cb328eac024d"
+    );
+  });
+
+  it("keyBatchMessage", () => {
+    assert.equal(
+      hex(keyBatchMessage(deviceId, merkleRoot, 6, 4)),
+      "2b1f0a4808d741f934cf656a7b3c92871e686b8fa69baa0bcb90cdce173a43da"
+    );
+  });
+
+  it("destructionCommitment", () => {
+    assert.equal(
+      hex(destructionCommitment(publicKey, reveal)),
+      "08b9fcbce0fe803f68cbebfc025a5145268a273618625c4e29e83becf4e2cc24"
+    );
+  });
+
+  it("destructionUseTag", () => {
+    assert.equal(
+      hex(destructionUseTag(reveal, transactionHash)),
+      "7f46ce0d91d26c92f29110a06ba430ca7fc298a7bf9e16bef07015245d83315b"
+    );
+  });
+
+  it("WOTS+ key generation and signing", () => {
+    const secretSeed = new Uint8Array(32).fill(0x11);
+    const publicSeed = new Uint8Array(32).fill(0x22);
+    const message = new Uint8Array(32).fill(0x33);
+
+    const wotsKey = wotsPublicKey(secretSeed, publicSeed, 5);
+    assert.equal(hex(wotsKey), "0c2d1ee20bf09ebfd6e25e2547f66bcf52e00ceeb27c5843f13e2d4021bf94be");
+
+    const signature = wotsSign(secretSeed, publicSeed, 5, message);
+    const signatureHash = createHash("sha256");
+    signature.forEach((value) => signatureHash.update(value));
+    assert.equal(
+      signatureHash.digest("hex"),
+      "a240bb8a296a26895c3deed33fecfab5486c0d9765b96de2f4809dce0f611c5c"
+    );
+    assert.equal(hex(wotsPublicKeyFromSignature(publicSeed, 5, message, signature)), hex(wotsKey));
+  });
+});